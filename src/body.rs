@@ -1,15 +1,28 @@
-use std::f32::consts::PI;
+use std::{collections::HashMap, f32::consts::PI};
 
 use itertools::Itertools;
 use lending_iterator::prelude::*;
-use macroquad::{prelude::*, rand};
+use macroquad::prelude::*;
+
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    constraints::{Constraint, ConstraintDescriptor},
+    constraints::{Constraint, ConstraintDescriptor, RegistryError},
     joint::{Joint, JointDescriptor},
+    random::Rng,
+    serialization,
+    stroke::{stroke_outline, LineJoin, Stroke},
 };
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+/// The most pieces an edge of the outline is split into. Past this, the outline doesn't look any
+/// smoother.
+pub const MAX_OUTLINE_SUBDIVISIONS: usize = 16;
+/// The most points a body's outline is smoothed up to. Bodies with many joints are subdivided less
+/// to stay within this, so that the fill of a body can be drawn in a single draw call. Bodies with
+/// more corners than this aren't smoothed at all.
+pub const MAX_OUTLINE_POINTS: usize = 1600;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum Side {
     #[default]
     Front,
@@ -20,12 +33,16 @@ pub enum Side {
 pub struct Body {
     pub line_color: Color,
     pub line_thickness: f32,
+    pub line_join: LineJoin,
     pub fill_color: Color,
     pub joints: Vec<Joint>,
     pub constraints: Vec<Box<dyn Constraint>>,
     pub attachment_angle: f32,
     pub attachment_offset: f32,
     pub side: Side,
+    /// How many pieces each straight edge of the outline is split into, following a smooth curve
+    /// through its corners. One keeps the outline faceted.
+    pub outline_subdivisions: usize,
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -35,32 +52,73 @@ pub struct AttachmentPoint {
 }
 
 impl Body {
+    /// Builds the body with the default seed. Use [`Body::new_with_rng`] to pick the seed.
+    ///
+    /// # Panics
+    ///
+    /// If any custom constraint can't be built. Use [`Body::try_new`] to handle this instead.
     pub fn new(descriptor: BodyDescriptor) -> Self {
+        Self::new_with_rng(descriptor, &mut Rng::default())
+    }
+
+    /// Builds the body, drawing any randomness from `rng`. The same seed and descriptor always
+    /// produce the same body.
+    ///
+    /// # Panics
+    ///
+    /// If any custom constraint can't be built. Use [`Body::try_new_with_rng`] to handle this
+    /// instead.
+    pub fn new_with_rng(descriptor: BodyDescriptor, rng: &mut Rng) -> Self {
+        Self::try_new_with_rng(descriptor, rng).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Builds the body with the default seed, failing if any custom constraint, including those
+    /// of inner bodies, can't be built.
+    pub fn try_new(descriptor: BodyDescriptor) -> Result<Self, RegistryError> {
+        Self::try_new_with_rng(descriptor, &mut Rng::default())
+    }
+
+    /// Builds the body like [`Body::new_with_rng`], failing if any custom constraint, including
+    /// those of inner bodies, can't be built.
+    pub fn try_new_with_rng(
+        descriptor: BodyDescriptor,
+        rng: &mut Rng,
+    ) -> Result<Self, RegistryError> {
         let BodyDescriptor {
             line_color,
             line_thickness,
+            line_join,
             fill_color,
             joints,
             constraints,
             attachment_angle,
             attachment_offset,
             side,
+            outline_subdivisions,
         } = descriptor;
 
         let mut init = Self {
             line_color,
             line_thickness,
+            line_join,
             fill_color,
-            joints: joints.into_iter().map(Into::into).collect::<Vec<_>>(),
-            constraints: constraints.into_iter().map(Into::into).collect::<Vec<_>>(),
+            joints: joints
+                .into_iter()
+                .map(|joint| Joint::try_new_with_rng(joint, rng))
+                .collect::<Result<Vec<_>, _>>()?,
+            constraints: constraints
+                .into_iter()
+                .map(ConstraintDescriptor::build)
+                .collect::<Result<Vec<_>, _>>()?,
             attachment_angle,
             attachment_offset,
             side,
+            outline_subdivisions,
         };
 
-        jitter(&mut init);
+        jitter(&mut init, rng);
 
-        init
+        Ok(init)
     }
 
     pub fn with_constraint(mut self, constraint: impl Constraint + 'static) -> Self {
@@ -72,9 +130,20 @@ impl Body {
         self.constraints.push(Box::new(constraint));
     }
 
-    pub fn apply_constraints(&mut self, attachment_point: Option<AttachmentPoint>) {
+    /// Steps the body, and all of its inner bodies, forward by `dt` seconds.
+    pub fn apply_constraints(&mut self, attachment_point: Option<AttachmentPoint>, dt: f32) {
+        if attachment_point.is_none() {
+            self.resolve_anchors();
+        }
+
+        // The first joint of an inner body is pinned to its parent, so it never drifts.
+        let pinned = usize::from(attachment_point.is_some());
+        for joint in self.joints.iter_mut().skip(pinned) {
+            joint.integrate(dt);
+        }
+
         for constrait in &mut self.constraints {
-            constrait.apply(&mut self.joints, attachment_point);
+            constrait.apply(&mut self.joints, attachment_point, dt);
         }
 
         // update joint angles
@@ -111,14 +180,55 @@ impl Body {
                 if let Some(first_joint) = body.joints.first_mut() {
                     first_joint.pos = attachment_point.position;
                 }
-                body.apply_constraints(Some(attachment_point));
+                body.apply_constraints(Some(attachment_point), dt);
             }
         }
     }
 
+    /// Tells every constraint in the tree where the named joints are, as of the last step.
+    fn resolve_anchors(&mut self) {
+        let mut anchors = HashMap::new();
+        self.visit_joints(&mut |joint| {
+            if let Some(name) = &joint.name {
+                anchors.insert(name.clone(), joint.pos);
+            }
+        });
+        if anchors.is_empty() {
+            return;
+        }
+
+        self.visit_bodies_mut(&mut |body| {
+            for constraint in &mut body.constraints {
+                constraint.resolve_anchors(&anchors);
+            }
+        });
+    }
+
     /// Produces a zig-zag of points. This is useful for tessellation, but a bit painful for line
-    /// drawing. Trade-offs!
-    fn points(&self) -> Vec<Vec2> {
+    /// drawing. Trade-offs! Each side of the zig-zag is smoothed according to
+    /// [`Body::outline_subdivisions`], as far as [`MAX_OUTLINE_POINTS`] allows.
+    pub fn points(&self) -> Vec<Vec2> {
+        let points = self.corner_points();
+        // Each side of `corners` corners is subdivided into `(corners - 1) * subdivisions + 1`
+        // points.
+        let corners = points.len() / 2;
+        let subdivisions = self
+            .outline_subdivisions
+            .min(MAX_OUTLINE_SUBDIVISIONS)
+            .min((MAX_OUTLINE_POINTS / 2).saturating_sub(1) / corners.saturating_sub(1).max(1));
+        if subdivisions <= 1 {
+            return points;
+        }
+
+        // Both sides have as many corners, so they're subdivided into as many points and can be
+        // zipped back together.
+        let left = smooth(points.iter().step_by(2).copied(), subdivisions);
+        let right = smooth(points.iter().skip(1).step_by(2).copied(), subdivisions);
+        left.into_iter().interleave(right).collect()
+    }
+
+    /// The zig-zag of points around the joints, before any smoothing.
+    fn corner_points(&self) -> Vec<Vec2> {
         const END_STEPS: usize = 4;
 
         let first = self.joints.first();
@@ -160,57 +270,109 @@ impl Body {
             .collect::<Vec<_>>()
     }
 
-    fn mesh<'a, T>(&self, points: T) -> Mesh
-    where
-        T: IntoIterator<Item = &'a Vec2>,
-    {
-        let vertices = points
-            .into_iter()
-            .map(|point| Vertex::new2(point.extend(0.0), Vec2::ZERO, self.fill_color))
-            .collect::<Vec<_>>();
-        let index_count = (vertices.len() - 2).max(0) / 2;
-        let indices = (0..index_count)
+    /// The line around the zig-zag produced by [`Body::points`]. Only the outer half of the line
+    /// is visible past the fill, so the stroke is that half alone.
+    pub fn stroke(&self, points: &[Vec2]) -> Stroke {
+        if self.line_thickness <= 0. {
+            return Stroke::default();
+        }
+
+        stroke_outline(
+            &Self::outline_of(points),
+            self.line_thickness / 2.,
+            self.line_join,
+        )
+    }
+
+    /// Triangle indices which fill the zig-zag produced by [`Body::points`].
+    pub fn fill_indices(point_count: usize) -> Vec<u32> {
+        let index_count = point_count.saturating_sub(2) / 2;
+        (0..index_count)
             .flat_map(|i| {
-                let s = (i as u16) * 2;
+                let s = (i as u32) * 2;
                 [s, s + 1, s + 2, s + 1, s + 2, s + 3]
             })
-            .collect::<Vec<_>>();
+            .collect::<Vec<_>>()
+    }
 
-        Mesh {
-            vertices,
-            indices,
-            texture: None,
+    /// The closed outline of the body, going down one side and back up the other.
+    pub fn outline(&self) -> Vec<Vec2> {
+        Self::outline_of(&self.points())
+    }
+
+    /// The closed outline around the zig-zag produced by [`Body::points`].
+    fn outline_of(points: &[Vec2]) -> Vec<Vec2> {
+        points
+            .iter()
+            .step_by(2)
+            .chain(points.iter().skip(1).step_by(2).rev())
+            .copied()
+            .collect()
+    }
+
+    /// Visits this body and all of its inner bodies in the order in which they are drawn: bodies
+    /// on the back side, then this body, then bodies on the front side.
+    pub fn visit_in_draw_order<'a>(&'a self, visitor: &mut impl FnMut(&'a Body)) {
+        for side in [Side::Back, Side::Front] {
+            if side == Side::Front {
+                visitor(self);
+            }
+
+            for joint in &self.joints {
+                for body in joint.bodies.iter().filter(|body| body.side == side) {
+                    body.visit_in_draw_order(visitor);
+                }
+            }
         }
     }
 
-    pub fn draw(&self) {
+    /// Visits every joint of this body and its inner bodies, depth first.
+    pub fn visit_joints<'a>(&'a self, visitor: &mut impl FnMut(&'a Joint)) {
         for joint in &self.joints {
-            joint.draw(Side::Back);
+            visitor(joint);
+            for body in &joint.bodies {
+                body.visit_joints(visitor);
+            }
         }
+    }
 
-        let points = self.points();
+    /// Visits every joint of this body and its inner bodies, in the same order as
+    /// [`Body::visit_joints`].
+    pub fn visit_joints_mut(&mut self, visitor: &mut impl FnMut(&mut Joint)) {
+        for joint in &mut self.joints {
+            visitor(joint);
+            for body in &mut joint.bodies {
+                body.visit_joints_mut(visitor);
+            }
+        }
+    }
 
-        if self.line_thickness > 0. {
-            for (a, b) in points
-                .iter()
-                .step_by(2)
-                .tuple_windows()
-                .chain(points.iter().skip(1).step_by(2).tuple_windows())
-            {
-                draw_circle(a.x, a.y, self.line_thickness / 2., self.line_color);
-                draw_line(a.x, a.y, b.x, b.y, self.line_thickness, self.line_color);
-                draw_circle(b.x, b.y, self.line_thickness / 2., self.line_color);
+    /// Visits this body and all of its inner bodies, depth first.
+    pub fn visit_bodies_mut(&mut self, visitor: &mut impl FnMut(&mut Body)) {
+        visitor(self);
+        for joint in &mut self.joints {
+            for body in &mut joint.bodies {
+                body.visit_bodies_mut(visitor);
             }
         }
+    }
+
+    #[cfg(feature = "render")]
+    pub fn draw(&self) {
+        self.visit_in_draw_order(&mut |body| body.draw_shape());
+    }
 
-        let mesh = self.mesh(&points);
-        draw_mesh(&mesh);
+    /// Draws this body alone, without any of its inner bodies.
+    #[cfg(feature = "render")]
+    fn draw_shape(&self) {
+        let points = self.points();
 
-        for joint in &self.joints {
-            joint.draw(Side::Front);
-        }
+        let stroke = self.stroke(&points);
+        draw_triangles(&stroke.vertices, &stroke.indices, self.line_color);
+        draw_triangles(&points, &Self::fill_indices(points.len()), self.fill_color);
     }
 
+    #[cfg(feature = "render")]
     pub fn debug_draw(&self) {
         for joint in &self.joints {
             joint.debug_draw();
@@ -222,16 +384,23 @@ impl Body {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct BodyDescriptor {
+    #[serde(with = "serialization::color")]
     pub line_color: Color,
     pub line_thickness: f32,
+    pub line_join: LineJoin,
+    #[serde(with = "serialization::color")]
     pub fill_color: Color,
     pub joints: Vec<JointDescriptor>,
     pub constraints: Vec<ConstraintDescriptor>,
     pub attachment_angle: f32,
     pub attachment_offset: f32,
     pub side: Side,
+    /// How many pieces each straight edge of the outline is split into, following a smooth curve
+    /// through its corners. One keeps the outline faceted.
+    pub outline_subdivisions: usize,
 }
 
 impl Default for BodyDescriptor {
@@ -239,12 +408,14 @@ impl Default for BodyDescriptor {
         Self {
             line_color: BLACK,
             line_thickness: 6.0,
+            line_join: LineJoin::Round,
             fill_color: WHITE,
             joints: Vec::new(),
             constraints: Vec::new(),
             attachment_angle: 0.0,
             attachment_offset: 0.0,
             side: Side::Front,
+            outline_subdivisions: 4,
         }
     }
 }
@@ -266,15 +437,161 @@ impl From<BodyDescriptor> for Body {
     }
 }
 
-// Randomize each joint to ensure that the body is always rendered correctly.
-fn jitter(body: &mut Body) {
+/// The most indices drawn in a single call. macroquad drops anything from 5000 indices on, so
+/// this is the most whole triangles below that.
+#[cfg(feature = "render")]
+const MAX_DRAW_INDICES: usize = 4998;
+
+/// Draws the triangles in a single colour, splitting them over several draw calls when there are
+/// too many for one.
+#[cfg(feature = "render")]
+fn draw_triangles(vertices: &[Vec2], indices: &[u32], color: Color) {
+    let vertex = |point: Vec2| Vertex::new2(point.extend(0.0), Vec2::ZERO, color);
+
+    if vertices.len() <= MAX_DRAW_INDICES && indices.len() <= MAX_DRAW_INDICES {
+        draw_mesh(&Mesh {
+            vertices: vertices.iter().copied().map(vertex).collect(),
+            indices: indices.iter().map(|&index| index as u16).collect(),
+            texture: None,
+        });
+        return;
+    }
+
+    // Each chunk gets its own copy of the vertices it uses, so its indices always fit.
+    for chunk in indices.chunks(MAX_DRAW_INDICES) {
+        draw_mesh(&Mesh {
+            vertices: chunk
+                .iter()
+                .map(|&index| vertex(vertices[index as usize]))
+                .collect(),
+            indices: (0..chunk.len() as u16).collect(),
+            texture: None,
+        });
+    }
+}
+
+// Randomize each joint to ensure that the body is always rendered correctly. Inner bodies are
+// jittered as they're built.
+fn jitter(body: &mut Body, rng: &mut Rng) {
     for joint in &mut body.joints {
-        joint.pos += Vec2::new(
-            rand::RandomRange::gen_range(-0.05, 0.05),
-            rand::RandomRange::gen_range(-0.05, 0.05),
+        joint.pos += Vec2::new(rng.gen_range(-0.05, 0.05), rng.gen_range(-0.05, 0.05));
+    }
+}
+
+/// Samples a centripetal Catmull-Rom spline through the points, with `subdivisions` samples per
+/// segment. Unlike a uniform spline, this doesn't overshoot or loop where the points are unevenly
+/// spaced, such as where the caps of the body meet its sides.
+fn smooth(points: impl Iterator<Item = Vec2>, subdivisions: usize) -> Vec<Vec2> {
+    let points = points.collect::<Vec<_>>();
+    let count = points.len();
+    if count < 3 {
+        return points;
+    }
+
+    let mut smoothed = Vec::with_capacity((count - 1) * subdivisions + 1);
+    for i in 0..count - 1 {
+        // The ends are extended by mirroring their neighbours.
+        let before = if i == 0 {
+            2. * points[0] - points[1]
+        } else {
+            points[i - 1]
+        };
+        let after = if i + 2 < count {
+            points[i + 2]
+        } else {
+            2. * points[count - 1] - points[count - 2]
+        };
+
+        let segment = [before, points[i], points[i + 1], after];
+        smoothed.extend(
+            (0..subdivisions).map(|j| catmull_rom(segment, j as f32 / subdivisions as f32)),
         );
-        for body in &mut joint.bodies {
-            jitter(body);
+    }
+    smoothed.push(points[count - 1]);
+    smoothed
+}
+
+/// The point `t` of the way from `p1` to `p2` on a centripetal Catmull-Rom spline.
+fn catmull_rom([p0, p1, p2, p3]: [Vec2; 4], t: f32) -> Vec2 {
+    if p1.distance_squared(p2) <= f32::EPSILON {
+        return p1;
+    }
+
+    let knot = |a: Vec2, b: Vec2| a.distance(b).sqrt().max(f32::EPSILON);
+    let t1 = knot(p0, p1);
+    let t2 = t1 + knot(p1, p2);
+    let t3 = t2 + knot(p2, p3);
+    let t = t1.lerp(t2, t);
+
+    let a1 = p0.lerp(p1, t / t1);
+    let a2 = p1.lerp(p2, (t - t1) / (t2 - t1));
+    let a3 = p2.lerp(p3, (t - t2) / (t3 - t2));
+    let b1 = a1.lerp(a2, t / t2);
+    let b2 = a2.lerp(a3, (t - t1) / (t3 - t1));
+    b1.lerp(b2, (t - t1) / (t2 - t1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constraints::{DistanceConstraintDescriptor, FabrikConstraintDescriptor};
+
+    fn descriptor() -> BodyDescriptor {
+        let leg = BodyDescriptor {
+            joints: vec![JointDescriptor::default(); 3],
+            attachment_angle: PI / 2.,
+            constraints: vec![ConstraintDescriptor::Fabrik(FabrikConstraintDescriptor {
+                target_angle: PI / 4.,
+                ..Default::default()
+            })],
+            ..Default::default()
+        };
+
+        BodyDescriptor {
+            joints: (0..8)
+                .map(|i| JointDescriptor {
+                    radius: 10.,
+                    bodies: if i == 2 {
+                        vec![leg.clone()]
+                    } else {
+                        Vec::new()
+                    },
+                    ..Default::default()
+                })
+                .collect(),
+            constraints: vec![ConstraintDescriptor::Distance(
+                DistanceConstraintDescriptor {
+                    distance: 15.,
+                    ..Default::default()
+                },
+            )],
+            ..Default::default()
+        }
+    }
+
+    /// Steps the body along a curve, returning the bits of every joint's position.
+    fn simulate(seed: u64) -> Vec<(u32, u32)> {
+        let mut body = Body::new_with_rng(descriptor(), &mut Rng::new(seed));
+        for step in 0..200 {
+            let t = step as f32 / 60.;
+            body.joints[0].pos = Vec2::new(100. * t, 40. * (2. * t).sin());
+            body.apply_constraints(None, 1. / 60.);
         }
+
+        let mut positions = Vec::new();
+        body.visit_joints(&mut |joint| {
+            positions.push((joint.pos.x.to_bits(), joint.pos.y.to_bits()))
+        });
+        positions
+    }
+
+    #[test]
+    fn same_seed_simulates_identically() {
+        assert_eq!(simulate(42), simulate(42));
+    }
+
+    #[test]
+    fn different_seeds_build_different_bodies() {
+        assert_ne!(simulate(1), simulate(2));
     }
 }
@@ -3,3 +3,8 @@ use macroquad::color::{Color, BLUE, WHITE};
 pub const DEBUG_LINE_THICKNESS: f32 = 2.0;
 pub const DEBUG_COLOR: Color = BLUE;
 pub const BACKGROUND_COLOR: Color = WHITE;
+
+/// The duration of a single simulation step, in seconds.
+pub const FIXED_TIMESTEP: f32 = 1.0 / 60.0;
+/// The time, in seconds, taken for the head to cover half of the distance to its target.
+pub const HEAD_HALF_LIFE: f32 = 0.11;
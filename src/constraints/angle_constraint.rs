@@ -1,53 +1,158 @@
 use super::Constraint;
-use crate::{body::AttachmentPoint, joint::Joint};
+use crate::{body::AttachmentPoint, joint::Joint, timestep::blend_factor};
 use lending_iterator::prelude::*;
 use macroquad::math::Vec2;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
 
-/// Enforces a minimum angle between 3 consecutive points.
+/// Which joints move to correct a bend.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AngleCorrection {
+    /// Only the joint after the bend moves.
+    #[default]
+    Forward,
+    /// Both neighbours of the bend rotate around it by the same amount.
+    Distributed,
+}
+
+/// The interior angles allowed at a joint, where a straight body is at PI.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AngleRange {
+    /// The smallest interior angle, in radians. This limits how far the joint bends.
+    pub min: f32,
+    /// The largest interior angle, in radians.
+    pub max: f32,
+    /// The interior angle the joint relaxes towards, if any.
+    pub rest: Option<f32>,
+    /// The time, in seconds, taken to relax half of the way to the rest angle. Smaller is stiffer.
+    pub rest_half_life: f32,
+}
+
+impl Default for AngleRange {
+    fn default() -> Self {
+        Self {
+            min: 0.,
+            max: PI,
+            rest: None,
+            rest_half_life: 0.1,
+        }
+    }
+}
+
+/// Enforces a range of angles between 3 consecutive points.
 #[derive(Clone, Debug)]
 pub struct AngleConstraint {
+    /// The smallest interior angle of joints without a range of their own.
     pub(crate) angle: f32,
-    /// The rate at which to apply the angle constraint.
-    pub(crate) rate: f32,
+    /// The range for each joint, from the second joint backwards, overriding `angle`.
+    pub(crate) ranges: Vec<AngleRange>,
+    pub(crate) correction: AngleCorrection,
+    /// The time, in seconds, taken to correct half of the error.
+    pub(crate) half_life: f32,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AngleConstraintDescriptor {
-    pub(crate) angle: f32,
-    /// The rate at which to apply the angle constraint.
-    pub(crate) rate: f32,
+    /// The smallest interior angle of joints without a range of their own.
+    pub angle: f32,
+    /// The range for each joint with a neighbour on either side, from the second joint backwards.
+    /// Joints past the end of the list are only kept above `angle`.
+    pub ranges: Vec<AngleRange>,
+    pub correction: AngleCorrection,
+    /// The time, in seconds, taken to correct half of the error.
+    pub half_life: f32,
+}
+
+impl Default for AngleConstraintDescriptor {
+    fn default() -> Self {
+        Self {
+            angle: 0.9 * PI,
+            ranges: Vec::new(),
+            correction: AngleCorrection::Forward,
+            half_life: 0.016,
+        }
+    }
 }
 
 impl AngleConstraint {
     pub fn new(descriptor: AngleConstraintDescriptor) -> Self {
-        let AngleConstraintDescriptor { angle, rate } = descriptor;
+        let AngleConstraintDescriptor {
+            angle,
+            ranges,
+            correction,
+            half_life,
+        } = descriptor;
 
-        Self { angle, rate }
+        Self {
+            angle,
+            ranges,
+            correction,
+            half_life,
+        }
+    }
+
+    fn range(&self, joint: usize) -> AngleRange {
+        self.ranges.get(joint).copied().unwrap_or(AngleRange {
+            min: self.angle,
+            ..Default::default()
+        })
+    }
+
+    /// Moves `a` and `c` so that the interior angle at `b` becomes `target`, `rate` of the way.
+    fn bend(&self, [a, b, c]: [&mut Joint; 3], target: f32, rate: f32) {
+        let ba = a.pos - b.pos;
+        let bc = c.pos - b.pos;
+
+        let direction = if ba.perp_dot(bc).is_sign_negative() {
+            -1.0
+        } else {
+            1.0
+        };
+
+        match self.correction {
+            AngleCorrection::Forward => {
+                let total_angle = ba.to_angle() + target * direction;
+                let target = b.pos + bc.length() * Vec2::from_angle(total_angle);
+
+                c.pos = c.pos.lerp(target, rate);
+            }
+            AngleCorrection::Distributed => {
+                let angle = ba.angle_between(bc).abs();
+                let half_correction = (target - angle) * rate * direction / 2.;
+                a.pos = b.pos + Vec2::from_angle(-half_correction).rotate(ba);
+                c.pos = b.pos + Vec2::from_angle(half_correction).rotate(bc);
+            }
+        }
     }
 }
 
 impl Constraint for AngleConstraint {
-    fn apply(&mut self, joints: &mut Vec<Joint>, _attachment_point: Option<AttachmentPoint>) {
+    fn apply(
+        &mut self,
+        joints: &mut Vec<Joint>,
+        _attachment_point: Option<AttachmentPoint>,
+        dt: f32,
+    ) {
+        let rate = blend_factor(self.half_life, dt);
+        let mut index = 0;
         let mut iter = joints.windows_mut::<3>();
         while let Some([a, b, c]) = iter.next() {
-            let ba = a.pos - b.pos;
-            let bc = c.pos - b.pos;
+            let range = self.range(index);
+            index += 1;
 
-            let angle = ba.angle_between(bc);
-            if angle.abs() >= self.angle {
-                continue;
+            if let Some(rest) = range.rest {
+                let rest_rate = blend_factor(range.rest_half_life, dt);
+                self.bend([&mut *a, &mut *b, &mut *c], rest, rest_rate);
             }
 
-            let direction = if ba.perp_dot(bc).is_sign_negative() {
-                -1.0
-            } else {
-                1.0
-            };
-
-            let total_angle = ba.to_angle() + self.angle * direction;
-            let target = b.pos + bc.length() * Vec2::from_angle(total_angle);
-
-            c.pos = c.pos.lerp(target, self.rate);
+            let angle = (a.pos - b.pos).angle_between(c.pos - b.pos).abs();
+            if angle < range.min {
+                self.bend([&mut *a, &mut *b, &mut *c], range.min, rate);
+            } else if angle > range.max {
+                self.bend([&mut *a, &mut *b, &mut *c], range.max, rate);
+            }
         }
     }
 }
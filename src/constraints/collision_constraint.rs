@@ -0,0 +1,155 @@
+use super::Constraint;
+use crate::{
+    body::{AttachmentPoint, Body},
+    joint::Joint,
+    spatial::SpatialGrid,
+    timestep::blend_factor,
+};
+use macroquad::math::Vec2;
+use serde::{Deserialize, Serialize};
+
+/// Pushes apart joints of the same body whose circles overlap. Joints which are close to each
+/// other in the chain always overlap, so they are skipped.
+#[derive(Clone, Debug)]
+pub struct CollisionConstraint {
+    /// Joints this many places apart, or closer, are not collided.
+    pub(crate) skip: usize,
+    /// The time, in seconds, taken to correct half of the overlap.
+    pub(crate) half_life: f32,
+    grid: SpatialGrid,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CollisionConstraintDescriptor {
+    /// Joints this many places apart, or closer, are not collided.
+    pub skip: usize,
+    /// The time, in seconds, taken to correct half of the overlap.
+    pub half_life: f32,
+}
+
+impl Default for CollisionConstraintDescriptor {
+    fn default() -> Self {
+        Self {
+            skip: 2,
+            half_life: 0.,
+        }
+    }
+}
+
+impl CollisionConstraint {
+    pub fn new(descriptor: CollisionConstraintDescriptor) -> Self {
+        let CollisionConstraintDescriptor { skip, half_life } = descriptor;
+
+        Self {
+            skip,
+            half_life,
+            grid: SpatialGrid::default(),
+        }
+    }
+}
+
+impl Constraint for CollisionConstraint {
+    fn apply(
+        &mut self,
+        joints: &mut Vec<Joint>,
+        _attachment_point: Option<AttachmentPoint>,
+        dt: f32,
+    ) {
+        let rate = blend_factor(self.half_life, dt);
+        let max_radius = joints.iter().map(|joint| joint.radius).fold(0., f32::max);
+
+        self.grid.reset(2. * max_radius);
+        for (i, joint) in joints.iter().enumerate() {
+            self.grid.insert(i, joint.pos);
+        }
+
+        let mut pairs = Vec::new();
+        for (i, joint) in joints.iter().enumerate() {
+            self.grid.query(joint.pos, joint.radius + max_radius, |j| {
+                if j > i + self.skip {
+                    pairs.push((i, j));
+                }
+            });
+        }
+
+        for (i, j) in pairs {
+            let (head, tail) = joints.split_at_mut(j);
+            let (a, b) = (&mut head[i], &mut tail[0]);
+            let offset = separation(a.pos, a.radius, b.pos, b.radius) * rate / 2.;
+            a.pos -= offset;
+            b.pos += offset;
+        }
+    }
+}
+
+/// Pushes apart overlapping joints of different bodies, such as every creature in a world. Joints
+/// of the same body are left to [`CollisionConstraint`]. `grid` is only passed in to reuse its
+/// allocations between calls.
+pub fn collide_bodies<'a>(
+    bodies: impl IntoIterator<Item = &'a mut Body>,
+    half_life: f32,
+    dt: f32,
+    grid: &mut SpatialGrid,
+) {
+    let mut bodies = bodies.into_iter().collect::<Vec<_>>();
+
+    // Flatten every joint, including those of inner bodies, so they can be bucketed together.
+    let mut circles = Vec::new();
+    for (owner, body) in bodies.iter().enumerate() {
+        body.visit_joints(&mut |joint| circles.push((owner, joint.pos, joint.radius)));
+    }
+
+    let max_radius = circles
+        .iter()
+        .map(|(_, _, radius)| *radius)
+        .fold(0., f32::max);
+    grid.reset(2. * max_radius);
+    for (i, (_, position, _)) in circles.iter().enumerate() {
+        grid.insert(i, *position);
+    }
+
+    // Accumulate the corrections before applying any of them, so the result doesn't depend on
+    // the order of the bodies.
+    let rate = blend_factor(half_life, dt);
+    let mut offsets = vec![Vec2::ZERO; circles.len()];
+    for (i, &(owner, position, radius)) in circles.iter().enumerate() {
+        grid.query(position, radius + max_radius, |j| {
+            let (other_owner, other_position, other_radius) = circles[j];
+            if j <= i || other_owner == owner {
+                return;
+            }
+
+            let offset = separation(position, radius, other_position, other_radius) * rate / 2.;
+            offsets[i] -= offset;
+            offsets[j] += offset;
+        });
+    }
+
+    let mut offsets = offsets.into_iter();
+    for body in &mut bodies {
+        body.visit_joints_mut(&mut |joint| {
+            if let Some(offset) = offsets.next() {
+                joint.pos += offset;
+            }
+        });
+    }
+}
+
+/// How far `b` needs to move away from `a` for their circles to stop overlapping.
+fn separation(a: Vec2, a_radius: f32, b: Vec2, b_radius: f32) -> Vec2 {
+    let delta = b - a;
+    let distance = delta.length();
+    let overlap = a_radius + b_radius - distance;
+    if overlap <= 0. {
+        return Vec2::ZERO;
+    }
+
+    // Coincident joints have no direction to separate in, so pick one.
+    let normal = if distance > f32::EPSILON {
+        delta / distance
+    } else {
+        Vec2::X
+    };
+    normal * overlap
+}
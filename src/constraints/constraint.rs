@@ -1,36 +1,97 @@
-use std::fmt::Debug;
+use std::{collections::HashMap, fmt::Debug};
 
 use dyn_clone::DynClone;
+use macroquad::math::Vec2;
+use ron::value::RawValue;
+use serde::{Deserialize, Serialize};
 
 use crate::{body::AttachmentPoint, joint::Joint};
 
 use super::{
-    AngleConstraint, AngleConstraintDescriptor, DistanceConstraint, DistanceConstraintDescriptor,
-    FabrikConstraint, FabrikConstraintDescriptor,
+    build_constraint, AngleConstraint, AngleConstraintDescriptor, CollisionConstraint,
+    CollisionConstraintDescriptor, DistanceConstraint, DistanceConstraintDescriptor,
+    FabrikConstraint, FabrikConstraintDescriptor, GaitConstraint, GaitConstraintDescriptor,
+    LegState, ObstacleConstraint, ObstacleConstraintDescriptor, PinConstraint,
+    PinConstraintDescriptor, RegistryError, SpringConstraint, SpringConstraintDescriptor,
 };
 
 pub trait Constraint: Debug + DynClone {
-    fn apply(&mut self, joints: &mut Vec<Joint>, attachment_point: Option<AttachmentPoint>);
+    fn apply(
+        &mut self,
+        joints: &mut Vec<Joint>,
+        attachment_point: Option<AttachmentPoint>,
+        dt: f32,
+    );
+    #[cfg(feature = "render")]
     fn debug_draw(&self) {}
+    /// The stepping state of the leg this constraint moves, if it moves one, so that a
+    /// [`GaitConstraint`] can coordinate it with the other legs.
+    fn leg_mut(&mut self) -> Option<&mut LegState> {
+        None
+    }
+    /// Called before each step with the positions of every named joint in the tree of bodies,
+    /// for constraints which refer to joints outside of their own body.
+    fn resolve_anchors(&mut self, _anchors: &HashMap<String, Vec2>) {}
 }
 
 dyn_clone::clone_trait_object!(Constraint);
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum ConstraintDescriptor {
     Distance(DistanceConstraintDescriptor),
     Angle(AngleConstraintDescriptor),
     Fabrik(FabrikConstraintDescriptor),
+    Collision(CollisionConstraintDescriptor),
+    Obstacle(ObstacleConstraintDescriptor),
+    Gait(GaitConstraintDescriptor),
+    Spring(SpringConstraintDescriptor),
+    Pin(PinConstraintDescriptor),
+    /// A constraint from the [`registry`](super::registry), such as one defined outside of this
+    /// crate, built from its serialized parameters.
+    Custom {
+        name: String,
+        #[serde(default = "empty_parameters")]
+        parameters: Box<RawValue>,
+    },
 }
 
-impl From<ConstraintDescriptor> for Box<dyn Constraint> {
-    fn from(descriptor: ConstraintDescriptor) -> Self {
-        match descriptor {
+/// Parameters for a custom constraint which takes all of its defaults.
+fn empty_parameters() -> Box<RawValue> {
+    RawValue::from_boxed_ron("()".into()).expect("`()` is valid RON")
+}
+
+impl ConstraintDescriptor {
+    /// Builds the constraint, failing if it's a custom constraint which isn't registered or whose
+    /// parameters don't match.
+    pub fn build(self) -> Result<Box<dyn Constraint>, RegistryError> {
+        Ok(match self {
             ConstraintDescriptor::Distance(descriptor) => {
                 Box::new(DistanceConstraint::new(descriptor))
             }
             ConstraintDescriptor::Angle(descriptor) => Box::new(AngleConstraint::new(descriptor)),
             ConstraintDescriptor::Fabrik(descriptor) => Box::new(FabrikConstraint::new(descriptor)),
-        }
+            ConstraintDescriptor::Collision(descriptor) => {
+                Box::new(CollisionConstraint::new(descriptor))
+            }
+            ConstraintDescriptor::Obstacle(descriptor) => {
+                Box::new(ObstacleConstraint::new(descriptor))
+            }
+            ConstraintDescriptor::Gait(descriptor) => Box::new(GaitConstraint::new(descriptor)),
+            ConstraintDescriptor::Spring(descriptor) => Box::new(SpringConstraint::new(descriptor)),
+            ConstraintDescriptor::Pin(descriptor) => Box::new(PinConstraint::new(descriptor)),
+            ConstraintDescriptor::Custom { name, parameters } => {
+                build_constraint(&name, &parameters)?
+            }
+        })
+    }
+}
+
+impl From<ConstraintDescriptor> for Box<dyn Constraint> {
+    /// # Panics
+    ///
+    /// If a custom constraint isn't registered, or its parameters don't match. Use
+    /// [`ConstraintDescriptor::build`] to handle this instead.
+    fn from(descriptor: ConstraintDescriptor) -> Self {
+        descriptor.build().unwrap_or_else(|err| panic!("{err}"))
     }
 }
@@ -1,9 +1,10 @@
-use crate::{body::AttachmentPoint, joint::Joint};
+use crate::{body::AttachmentPoint, joint::Joint, timestep::blend_factor};
 
 use super::Constraint;
 use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum Direction {
     #[default]
     Forward,
@@ -14,27 +15,34 @@ pub enum Direction {
 pub struct DistanceConstraint {
     /// The distance between each joint.
     pub(crate) distance: f32,
+    /// The distance for each segment, from the first joint backwards, overriding `distance`.
+    pub(crate) distances: Vec<f32>,
     /// The direction in which to apply the distance constraint.
     pub(crate) direction: Direction,
-    /// The rate at which to apply the distance constraint.
-    pub(crate) rate: f32,
+    /// The time, in seconds, taken to correct half of the error. Zero makes the constraint rigid.
+    pub(crate) half_life: f32,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct DistanceConstraintDescriptor {
     /// The distance between each joint.
-    pub(crate) distance: f32,
+    pub distance: f32,
+    /// The distance for each segment, from the first joint backwards. Segments past the end of the
+    /// list use `distance`.
+    pub distances: Vec<f32>,
     /// The direction in which to apply the distance constraint.
-    pub(crate) direction: Direction,
-    /// The rate at which to apply the distance constraint.
-    pub(crate) rate: f32,
+    pub direction: Direction,
+    /// The time, in seconds, taken to correct half of the error. Zero makes the constraint rigid.
+    pub half_life: f32,
 }
 
 impl Default for DistanceConstraintDescriptor {
     fn default() -> Self {
         Self {
             distance: 100.0,
-            rate: 1.0,
+            distances: Vec::new(),
+            half_life: 0.0,
             direction: Direction::Forward,
         }
     }
@@ -44,42 +52,99 @@ impl DistanceConstraint {
     pub fn new(descriptor: DistanceConstraintDescriptor) -> Self {
         let DistanceConstraintDescriptor {
             distance,
+            distances,
             direction,
-            rate,
+            half_life,
         } = descriptor;
 
         Self {
             distance,
+            distances,
             direction,
-            rate,
+            half_life,
         }
     }
 
-    fn apply_to_pair(&self, joint: &mut Joint, source: Vec2) {
+    /// The distance between the joint at `segment` and the one after it.
+    fn segment_distance(&self, segment: usize) -> f32 {
+        self.distances
+            .get(segment)
+            .copied()
+            .unwrap_or(self.distance)
+    }
+
+    fn apply_to_pair(&self, joint: &mut Joint, source: Vec2, segment: usize, rate: f32) {
         let delta = joint.pos - source;
 
         let distance = delta.length();
-        let target = source + self.distance * delta / distance;
+        let target = source + self.segment_distance(segment) * delta / distance;
 
-        joint.pos = joint.pos.lerp(target, self.rate);
+        joint.pos = joint.pos.lerp(target, rate);
     }
 }
 
 impl Constraint for DistanceConstraint {
-    fn apply(&mut self, joints: &mut Vec<Joint>, _attachment_point: Option<AttachmentPoint>) {
+    fn apply(
+        &mut self,
+        joints: &mut Vec<Joint>,
+        _attachment_point: Option<AttachmentPoint>,
+        dt: f32,
+    ) {
+        let rate = blend_factor(self.half_life, dt);
         match self.direction {
             Direction::Backwards => {
                 for i in (0..(joints.len() - 1)).rev() {
                     let source = joints[i + 1].pos;
-                    self.apply_to_pair(&mut joints[i], source);
+                    self.apply_to_pair(&mut joints[i], source, i, rate);
                 }
             }
             Direction::Forward => {
                 for i in 0..(joints.len() - 1) {
                     let source = joints[i].pos;
-                    self.apply_to_pair(&mut joints[i + 1], source);
+                    self.apply_to_pair(&mut joints[i + 1], source, i, rate);
                 }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        body::{Body, BodyDescriptor},
+        constraints::ConstraintDescriptor,
+        joint::JointDescriptor,
+    };
+
+    #[test]
+    fn keeps_segment_lengths() {
+        let mut body = Body::new(BodyDescriptor {
+            joints: vec![JointDescriptor::default(); 5],
+            constraints: vec![ConstraintDescriptor::Distance(
+                DistanceConstraintDescriptor {
+                    distance: 15.,
+                    distances: vec![10., 20.],
+                    ..Default::default()
+                },
+            )],
+            ..Default::default()
+        });
+
+        for step in 0..120 {
+            let t = step as f32 / 60.;
+            body.joints[0].pos = 80. * Vec2::new(t.cos(), (2. * t).sin());
+            body.apply_constraints(None, 1. / 60.);
+
+            for (segment, expected) in [10., 20., 15., 15.].into_iter().enumerate() {
+                let length = body.joints[segment]
+                    .pos
+                    .distance(body.joints[segment + 1].pos);
+                assert!(
+                    (length - expected).abs() < 1e-3,
+                    "segment {segment} is {length} long at step {step}, not {expected}",
+                );
+            }
+        }
+    }
+}
@@ -1,11 +1,17 @@
-use crate::{
-    body::AttachmentPoint,
-    extensions::{ColorExtension, ColorScalarExtension},
-    joint::Joint,
-};
+use std::f32::consts::PI;
 
-use super::{Constraint, Direction, DistanceConstraint};
+#[cfg(feature = "render")]
+use crate::extensions::{ColorExtension, ColorScalarExtension};
+use crate::{body::AttachmentPoint, joint::Joint};
+
+use super::{Constraint, Direction, DistanceConstraint, LegState};
 use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The furthest past the preferred target a foot may land, as a fraction of the step's length.
+/// Landing that far past leaves the foot as far from the target as the step was long, so from 1
+/// onwards every step triggers a longer one and the foot runs away.
+pub const MAX_OVERSHOOT: f32 = 0.9;
 
 #[derive(Clone, Debug, Default)]
 pub struct FabrikConstraint {
@@ -23,30 +29,137 @@ pub struct FabrikConstraint {
     pub(crate) current_target_position: Vec2,
     /// The preferred target position.
     pub(crate) preferred_target_position: Vec2,
+    /// How long, in seconds, the foot takes to move to a new target.
+    pub(crate) step_duration: f32,
+    /// How far the foot is lifted sideways, away from the body, halfway through a step.
+    pub(crate) step_height: f32,
+    /// How far past the preferred target the foot lands, as a fraction of the step's length.
+    pub(crate) overshoot: f32,
+    /// The bend allowed at each knee, from the hip outwards.
+    pub(crate) joint_limits: Vec<JointLimit>,
+    /// The step the foot is in the middle of, if any.
+    pub(crate) step: Option<Step>,
+    /// Whether the leg wants to, and may, step to the preferred target.
+    pub(crate) leg: LegState,
+}
+
+/// Which way a knee may bend. Positive bends turn from the x axis towards the y axis.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BendDirection {
+    #[default]
+    Either,
+    Positive,
+    Negative,
+}
+
+impl BendDirection {
+    /// The same bend, as seen in a mirror. Useful for the opposite leg of a pair.
+    pub fn flipped(self) -> Self {
+        match self {
+            BendDirection::Either => BendDirection::Either,
+            BendDirection::Positive => BendDirection::Negative,
+            BendDirection::Negative => BendDirection::Positive,
+        }
+    }
+}
+
+/// Limits how far a knee bends, relative to a straight leg.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JointLimit {
+    pub bend: BendDirection,
+    /// The smallest bend, in radians.
+    pub min_angle: f32,
+    /// The largest bend, in radians.
+    pub max_angle: f32,
 }
 
-#[derive(Clone, Debug)]
+impl Default for JointLimit {
+    fn default() -> Self {
+        Self {
+            bend: BendDirection::Either,
+            min_angle: 0.,
+            max_angle: PI,
+        }
+    }
+}
+
+impl JointLimit {
+    /// The closest allowed bend to `angle`. A knee bent the wrong way is flipped over first.
+    fn clamp(&self, angle: f32) -> f32 {
+        let sign = match self.bend {
+            BendDirection::Either if angle < 0. => -1.,
+            BendDirection::Either | BendDirection::Positive => 1.,
+            BendDirection::Negative => -1.,
+        };
+        sign * angle
+            .abs()
+            .clamp(self.min_angle, self.max_angle.max(self.min_angle))
+    }
+}
+
+/// A foot travelling from its old target to the new one.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Step {
+    from: Vec2,
+    to: Vec2,
+    /// The offset of the foot from the straight line halfway through the step.
+    lift: Vec2,
+    /// The time, in seconds, since the step started.
+    elapsed: f32,
+}
+
+impl Step {
+    /// The position of the foot, `t` of the way through the step.
+    fn position(&self, t: f32) -> Vec2 {
+        let t = t.clamp(0., 1.);
+        let eased = t * t * (3. - 2. * t);
+        self.from.lerp(self.to, eased) + self.lift * (PI * t).sin()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct FabrikConstraintDescriptor {
     /// The distance between each joint/joint.
     pub joint_distance: f32,
-    /// The rate at which to apply the distance constraint in each direction.
-    pub rate: f32,
+    /// The time, in seconds, taken for the backward pass to correct half of the error.
+    pub half_life: f32,
     /// The angle of the target relative to the body.
     pub target_angle: f32,
     /// The desired distance between the first and last point.
     pub target_distance: f32,
     /// Max distance from the current target and the desired target.
     pub max_distance: f32,
+    /// How long, in seconds, the foot takes to move to a new target. Zero moves it instantly.
+    pub step_duration: f32,
+    /// How far the foot is lifted sideways, away from the body, halfway through a step.
+    pub step_height: f32,
+    /// How far past the preferred target the foot lands, as a fraction of the step's length.
+    /// Between zero and [`MAX_OVERSHOOT`], and clamped to that range.
+    pub overshoot: f32,
+    /// The bend allowed at each knee, from the hip outwards. Knees past the end of the list use
+    /// its last entry, and an empty list leaves every knee free.
+    pub joint_limits: Vec<JointLimit>,
+    /// Which pair of legs this leg belongs to, for the [`GaitConstraint`](super::GaitConstraint)
+    /// of the body it's attached to. Pairs are numbered from front to back, and hold at most two
+    /// legs. Without one, legs are paired up in the order they're attached.
+    pub pair: Option<usize>,
 }
 
 impl Default for FabrikConstraintDescriptor {
     fn default() -> Self {
         Self {
             joint_distance: 10.,
-            rate: 0.25,
+            half_life: 0.04,
             target_angle: 0.,
             target_distance: 20.,
             max_distance: 20.,
+            step_duration: 0.15,
+            step_height: 10.,
+            overshoot: 0.2,
+            joint_limits: Vec::new(),
+            pair: None,
         }
     }
 }
@@ -55,10 +168,15 @@ impl FabrikConstraint {
     pub fn new(descriptor: FabrikConstraintDescriptor) -> Self {
         let FabrikConstraintDescriptor {
             joint_distance,
-            rate,
+            half_life,
             target_angle,
             target_distance,
             max_distance,
+            step_duration,
+            step_height,
+            overshoot,
+            joint_limits,
+            pair,
         } = descriptor;
 
         Self {
@@ -67,24 +185,102 @@ impl FabrikConstraint {
             max_distance,
             current_target_position: Vec2::ZERO,
             preferred_target_position: Vec2::ZERO,
+            step_duration,
+            step_height,
+            overshoot: overshoot.clamp(0., MAX_OVERSHOOT),
+            joint_limits,
+            step: None,
+            leg: LegState {
+                pair,
+                ..Default::default()
+            },
             forward_distance_constraint: DistanceConstraint {
                 distance: joint_distance,
-                rate: 1.0,
+                distances: Vec::new(),
+                half_life: 0.0,
                 direction: Direction::Forward,
-                ..Default::default()
             },
             backward_distance_constraint: DistanceConstraint {
                 distance: joint_distance,
-                rate,
+                distances: Vec::new(),
+                half_life,
                 direction: Direction::Backwards,
-                ..Default::default()
             },
         }
     }
+
+    /// Plants the foot past the preferred target, animating it there if the step takes any time.
+    fn start_step(&mut self, attachment_position: Vec2) {
+        let from = self.current_target_position;
+        let to = self.preferred_target_position
+            + (self.preferred_target_position - from) * self.overshoot;
+        self.current_target_position = to;
+
+        if self.step_duration <= 0. {
+            return;
+        }
+
+        // Lift the foot towards whichever side of the step is further from the body.
+        let mut side = (to - from).perp().normalize_or_zero();
+        if side.dot((from + to) / 2. - attachment_position) < 0. {
+            side = -side;
+        }
+
+        self.step = Some(Step {
+            from,
+            to,
+            lift: side * self.step_height,
+            elapsed: 0.,
+        });
+    }
+
+    fn joint_limit(&self, knee: usize) -> Option<&JointLimit> {
+        self.joint_limits
+            .get(knee - 1)
+            .or_else(|| self.joint_limits.last())
+    }
+
+    /// Bends each knee back within its limits, by rotating the part of the leg on the side given
+    /// by `direction` around it, so that the joint distances are kept.
+    fn limit_joints(&self, joints: &mut [Joint], direction: Direction) {
+        let knees = 1..joints.len().saturating_sub(1);
+        let knees = match direction {
+            Direction::Forward => knees.collect::<Vec<_>>(),
+            Direction::Backwards => knees.rev().collect::<Vec<_>>(),
+        };
+
+        for knee in knees {
+            let Some(limit) = self.joint_limit(knee) else {
+                return;
+            };
+
+            let pivot = joints[knee].pos;
+            let incoming = pivot - joints[knee - 1].pos;
+            let outgoing = joints[knee + 1].pos - pivot;
+            let angle = incoming.perp_dot(outgoing).atan2(incoming.dot(outgoing));
+            let correction = limit.clamp(angle) - angle;
+            if correction == 0. {
+                continue;
+            }
+
+            let (rotation, moved) = match direction {
+                Direction::Forward => (Vec2::from_angle(correction), &mut joints[knee + 1..]),
+                Direction::Backwards => (Vec2::from_angle(-correction), &mut joints[..knee]),
+            };
+            for joint in moved {
+                joint.pos = pivot + rotation.rotate(joint.pos - pivot);
+            }
+        }
+    }
 }
 
 impl Constraint for FabrikConstraint {
-    fn apply(&mut self, joints: &mut Vec<Joint>, attachment_point: Option<AttachmentPoint>) {
+    fn apply(
+        &mut self,
+        joints: &mut Vec<Joint>,
+        attachment_point: Option<AttachmentPoint>,
+        dt: f32,
+    ) {
         let Some(attachment_point) = attachment_point else {
             return;
         };
@@ -99,30 +295,49 @@ impl Constraint for FabrikConstraint {
 
         self.preferred_target_position = attachment_point.position
             + self.target_distance * Vec2::from_angle(attachment_point.angle + self.target_angle);
-        if self
-            .current_target_position
-            .distance(self.preferred_target_position)
-            > self.max_distance
-            || first.pos.distance(last.pos) > self.target_distance
-        {
-            self.current_target_position = self.preferred_target_position;
+        self.leg.wants_step = self.step.is_none()
+            && (self
+                .current_target_position
+                .distance(self.preferred_target_position)
+                > self.max_distance
+                || first.pos.distance(last.pos) > self.target_distance);
+        if self.leg.wants_step && self.leg.may_step {
+            self.start_step(attachment_point.position);
+            self.leg.since_step = 0.;
+        } else {
+            self.leg.since_step += dt;
         }
-        let target_position = self.current_target_position;
+
+        let target_position = match &mut self.step {
+            Some(step) => {
+                step.elapsed += dt;
+                let position = step.position(step.elapsed / self.step_duration);
+                if step.elapsed >= self.step_duration {
+                    self.step = None;
+                }
+                position
+            }
+            None => self.current_target_position,
+        };
+        self.leg.planted = self.step.is_none();
 
         let Some(last) = joints.last_mut() else {
             return;
         };
         last.pos = target_position;
 
-        self.backward_distance_constraint.apply(joints, None);
+        self.backward_distance_constraint.apply(joints, None, dt);
+        self.limit_joints(joints, Direction::Backwards);
 
         if let Some(first) = joints.first_mut() {
             first.pos = attachment_point.position;
         }
 
-        self.forward_distance_constraint.apply(joints, None);
+        self.forward_distance_constraint.apply(joints, None, dt);
+        self.limit_joints(joints, Direction::Forward);
     }
 
+    #[cfg(feature = "render")]
     fn debug_draw(&self) {
         let intensity = self
             .current_target_position
@@ -153,5 +368,22 @@ impl Constraint for FabrikConstraint {
             5.0,
             GREEN,
         );
+
+        if let Some(step) = &self.step {
+            const SEGMENTS: usize = 12;
+            let mut previous = step.from;
+            for i in 1..=SEGMENTS {
+                let point = step.position(i as f32 / SEGMENTS as f32);
+                draw_line(previous.x, previous.y, point.x, point.y, 2.0, ORANGE);
+                previous = point;
+            }
+
+            let foot = step.position(step.elapsed / self.step_duration);
+            draw_circle(foot.x, foot.y, 4.0, ORANGE);
+        }
+    }
+
+    fn leg_mut(&mut self) -> Option<&mut LegState> {
+        Some(&mut self.leg)
     }
 }
@@ -0,0 +1,171 @@
+use super::Constraint;
+use crate::{body::AttachmentPoint, joint::Joint};
+use serde::{Deserialize, Serialize};
+
+/// The order in which a body's legs are allowed to step.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GaitPattern {
+    /// Every leg steps whenever it likes.
+    Free,
+    /// The two legs of each pair never step at the same time.
+    #[default]
+    Alternating,
+    /// Legs are split into two sets, zig-zagging from front to back, and the sets take turns.
+    Tripod,
+    /// Only one leg steps at a time, taking turns from back to front.
+    Wave,
+}
+
+impl GaitPattern {
+    /// Whether the legs at these positions are kept from stepping at the same time.
+    fn conflicts(self, a: LegPosition, b: LegPosition) -> bool {
+        let set = |leg: LegPosition| (leg.pair + leg.side) % 2;
+        match self {
+            GaitPattern::Free => false,
+            GaitPattern::Alternating => a != b && a.pair == b.pair,
+            GaitPattern::Tripod => set(a) != set(b),
+            GaitPattern::Wave => a != b,
+        }
+    }
+}
+
+/// Where a leg is on the body.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct LegPosition {
+    /// The pair the leg belongs to, counted from front to back.
+    pair: usize,
+    /// Whether the leg is the first or second of its pair to be attached.
+    side: usize,
+}
+
+/// The stepping state of a leg, shared between the constraint which moves the leg and the
+/// [`GaitConstraint`] of the body it's attached to.
+#[derive(Clone, Debug)]
+pub struct LegState {
+    /// Which pair of legs this leg belongs to, counted from front to back. Legs without one are
+    /// paired up in the order they're attached.
+    pub pair: Option<usize>,
+    /// Whether the leg has strayed far enough from where it wants to be that it needs to step.
+    pub wants_step: bool,
+    /// Whether the leg is allowed to step.
+    pub may_step: bool,
+    /// Whether the foot is on the ground, rather than in the middle of a step.
+    pub planted: bool,
+    /// The time, in seconds, since the leg last stepped.
+    pub since_step: f32,
+}
+
+impl Default for LegState {
+    fn default() -> Self {
+        Self {
+            pair: None,
+            wants_step: false,
+            may_step: true,
+            planted: true,
+            since_step: f32::INFINITY,
+        }
+    }
+}
+
+/// Coordinates the legs attached to the joints of a body, so that a leg only steps once the legs
+/// it conflicts with have planted their feet.
+#[derive(Clone, Debug, Default)]
+pub struct GaitConstraint {
+    pub(crate) pattern: GaitPattern,
+    /// How long, in seconds, a leg counts as mid-step after it starts a step, even if its foot
+    /// has already landed.
+    pub(crate) step_duration: f32,
+    /// The leg with the highest priority to step next.
+    turn: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GaitConstraintDescriptor {
+    pub pattern: GaitPattern,
+    /// How long, in seconds, a leg counts as mid-step after it starts a step, even if its foot
+    /// has already landed.
+    pub step_duration: f32,
+}
+
+impl Default for GaitConstraintDescriptor {
+    fn default() -> Self {
+        Self {
+            pattern: GaitPattern::default(),
+            step_duration: 0.15,
+        }
+    }
+}
+
+impl GaitConstraint {
+    pub fn new(descriptor: GaitConstraintDescriptor) -> Self {
+        let GaitConstraintDescriptor {
+            pattern,
+            step_duration,
+        } = descriptor;
+
+        Self {
+            pattern,
+            step_duration,
+            turn: 0,
+        }
+    }
+}
+
+impl Constraint for GaitConstraint {
+    fn apply(
+        &mut self,
+        joints: &mut Vec<Joint>,
+        _attachment_point: Option<AttachmentPoint>,
+        _dt: f32,
+    ) {
+        let mut legs = joints
+            .iter_mut()
+            .flat_map(|joint| joint.bodies.iter_mut())
+            .filter_map(|body| {
+                body.constraints
+                    .iter_mut()
+                    .find_map(|constraint| constraint.leg_mut())
+            })
+            .collect::<Vec<_>>();
+
+        let mut positions = Vec::<LegPosition>::with_capacity(legs.len());
+        for (i, leg) in legs.iter().enumerate() {
+            let pair = leg.pair.unwrap_or(i / 2);
+            let side = positions.iter().filter(|other| other.pair == pair).count();
+            positions.push(LegPosition { pair, side });
+        }
+
+        if self.pattern == GaitPattern::Wave {
+            legs.reverse();
+            positions.reverse();
+        }
+
+        let count = legs.len();
+        if count == 0 || self.pattern == GaitPattern::Free {
+            for leg in legs {
+                leg.may_step = true;
+            }
+            return;
+        }
+
+        let mut stepping = legs
+            .iter()
+            .map(|leg| !leg.planted || leg.since_step < self.step_duration)
+            .collect::<Vec<_>>();
+
+        // Hand out permission to step in order of priority, so that legs which were skipped over
+        // get the first chance next time.
+        let start = self.turn % count;
+        for i in (0..count).map(|offset| (start + offset) % count) {
+            let blocked = (0..count)
+                .any(|j| stepping[j] && self.pattern.conflicts(positions[i], positions[j]));
+            let leg = &mut legs[i];
+            leg.may_step = leg.wants_step && !blocked;
+            if leg.may_step {
+                stepping[i] = true;
+                self.turn = i + 1;
+            }
+        }
+    }
+}
@@ -1,10 +1,22 @@
 mod angle_constraint;
+mod collision_constraint;
 mod constraint;
 mod distance_constraint;
 mod fabrik_constraint;
+mod gait_constraint;
+mod obstacle_constraint;
+mod pin_constraint;
+mod registry;
+mod spring_constraint;
 
 pub use constraint::*;
 
 pub use angle_constraint::*;
+pub use collision_constraint::*;
 pub use distance_constraint::*;
 pub use fabrik_constraint::*;
+pub use gait_constraint::*;
+pub use obstacle_constraint::*;
+pub use pin_constraint::*;
+pub use registry::*;
+pub use spring_constraint::*;
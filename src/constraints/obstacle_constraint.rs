@@ -0,0 +1,59 @@
+use super::Constraint;
+use crate::{
+    body::AttachmentPoint,
+    joint::Joint,
+    obstacle::{avoid_obstacles, Obstacle},
+    timestep::blend_factor,
+};
+use serde::{Deserialize, Serialize};
+
+/// Keeps the circle of every joint outside of a set of static obstacles.
+#[derive(Clone, Debug, Default)]
+pub struct ObstacleConstraint {
+    pub(crate) obstacles: Vec<Obstacle>,
+    /// The time, in seconds, taken to correct half of the overlap.
+    pub(crate) half_life: f32,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ObstacleConstraintDescriptor {
+    pub obstacles: Vec<Obstacle>,
+    /// The time, in seconds, taken to correct half of the overlap.
+    pub half_life: f32,
+}
+
+impl ObstacleConstraint {
+    pub fn new(descriptor: ObstacleConstraintDescriptor) -> Self {
+        let ObstacleConstraintDescriptor {
+            obstacles,
+            half_life,
+        } = descriptor;
+
+        Self {
+            obstacles,
+            half_life,
+        }
+    }
+}
+
+impl Constraint for ObstacleConstraint {
+    fn apply(
+        &mut self,
+        joints: &mut Vec<Joint>,
+        _attachment_point: Option<AttachmentPoint>,
+        dt: f32,
+    ) {
+        let rate = blend_factor(self.half_life, dt);
+        for joint in joints {
+            avoid_obstacles(joint, &self.obstacles, rate);
+        }
+    }
+
+    #[cfg(feature = "render")]
+    fn debug_draw(&self) {
+        for obstacle in &self.obstacles {
+            obstacle.debug_draw();
+        }
+    }
+}
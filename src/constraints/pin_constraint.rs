@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+#[cfg(feature = "render")]
+use crate::constants::{DEBUG_COLOR, DEBUG_LINE_THICKNESS};
+use crate::{body::AttachmentPoint, joint::Joint, timestep::blend_factor};
+
+use super::Constraint;
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// What a pinned joint is held to.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PinTarget {
+    /// A fixed point in the world.
+    Position(Vec2),
+    /// The joint with this name, anywhere in the same tree of bodies.
+    Joint(String),
+}
+
+impl Default for PinTarget {
+    fn default() -> Self {
+        PinTarget::Position(Vec2::ZERO)
+    }
+}
+
+/// Holds one of the body's joints to a point in the world or to another joint, like a tether.
+#[derive(Clone, Debug, Default)]
+pub struct PinConstraint {
+    /// The index of the pinned joint.
+    pub(crate) joint: usize,
+    pub(crate) target: PinTarget,
+    /// How far the joint may stray from the target before it's pulled back. Zero pins it in place.
+    pub(crate) distance: f32,
+    /// The time, in seconds, taken to correct half of the error. Zero makes the pin rigid.
+    pub(crate) half_life: f32,
+    /// Where the target was found the last time the pin was applied.
+    position: Option<Vec2>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PinConstraintDescriptor {
+    /// The index of the pinned joint.
+    pub joint: usize,
+    pub target: PinTarget,
+    /// How far the joint may stray from the target before it's pulled back. Zero pins it in place.
+    pub distance: f32,
+    /// The time, in seconds, taken to correct half of the error. Zero makes the pin rigid.
+    pub half_life: f32,
+}
+
+impl PinConstraint {
+    pub fn new(descriptor: PinConstraintDescriptor) -> Self {
+        let PinConstraintDescriptor {
+            joint,
+            target,
+            distance,
+            half_life,
+        } = descriptor;
+
+        Self {
+            joint,
+            position: match target {
+                PinTarget::Position(position) => Some(position),
+                PinTarget::Joint(_) => None,
+            },
+            target,
+            distance,
+            half_life,
+        }
+    }
+}
+
+impl Constraint for PinConstraint {
+    fn apply(
+        &mut self,
+        joints: &mut Vec<Joint>,
+        _attachment_point: Option<AttachmentPoint>,
+        dt: f32,
+    ) {
+        let (Some(joint), Some(position)) = (joints.get_mut(self.joint), self.position) else {
+            return;
+        };
+
+        let delta = joint.pos - position;
+        if delta.length() <= self.distance {
+            return;
+        }
+
+        let target = position + delta.clamp_length_max(self.distance);
+        joint.pos = joint.pos.lerp(target, blend_factor(self.half_life, dt));
+    }
+
+    fn resolve_anchors(&mut self, anchors: &HashMap<String, Vec2>) {
+        if let PinTarget::Joint(name) = &self.target {
+            self.position = anchors.get(name).copied();
+        }
+    }
+
+    #[cfg(feature = "render")]
+    fn debug_draw(&self) {
+        let Some(position) = self.position else {
+            return;
+        };
+
+        draw_circle_lines(
+            position.x,
+            position.y,
+            self.distance.max(4.),
+            DEBUG_LINE_THICKNESS,
+            DEBUG_COLOR,
+        );
+    }
+}
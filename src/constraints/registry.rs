@@ -0,0 +1,198 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{OnceLock, RwLock},
+};
+
+use ron::{error::SpannedError, value::RawValue};
+use serde::de::DeserializeOwned;
+
+use super::{Constraint, ConstraintDescriptor};
+
+type ConstraintFactory =
+    Box<dyn Fn(&RawValue) -> Result<Box<dyn Constraint>, SpannedError> + Send + Sync>;
+/// Turns the parameters of a built-in constraint into its descriptor.
+type BuiltinParser =
+    Box<dyn Fn(&RawValue) -> Result<ConstraintDescriptor, SpannedError> + Send + Sync>;
+
+/// Maps constraint type names to factories which build them from serialized parameters, so that
+/// constraints defined outside of this crate can appear in a
+/// [`ConstraintDescriptor`](super::ConstraintDescriptor).
+pub struct ConstraintRegistry {
+    factories: HashMap<String, ConstraintFactory>,
+    /// The built-in constraints which are still registered under their own name, so that they
+    /// can be checked in the same way as their descriptor variant.
+    builtins: HashMap<String, BuiltinParser>,
+}
+
+#[derive(Debug)]
+pub enum RegistryError {
+    /// No constraint is registered with this name.
+    Unknown(String),
+    /// The parameters don't match what the constraint expects.
+    Parameters {
+        name: String,
+        err: Box<SpannedError>,
+    },
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::Unknown(name) => write!(f, "no constraint is registered as `{name}`"),
+            RegistryError::Parameters { name, err } => {
+                write!(f, "invalid parameters for constraint `{name}`: {err}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+impl ConstraintRegistry {
+    /// A registry without any constraints, not even the built-in ones.
+    pub fn empty() -> Self {
+        Self {
+            factories: HashMap::new(),
+            builtins: HashMap::new(),
+        }
+    }
+
+    /// Registers a constraint which is built from parameters of type `D`, replacing any
+    /// constraint already registered with the same name.
+    pub fn register<D, C>(
+        &mut self,
+        name: impl Into<String>,
+        build: impl Fn(D) -> C + Send + Sync + 'static,
+    ) where
+        D: DeserializeOwned,
+        C: Constraint + 'static,
+    {
+        let name = name.into();
+        self.builtins.remove(&name);
+        self.factories.insert(
+            name,
+            Box::new(move |parameters| {
+                let descriptor = parameters.into_rust::<D>()?;
+                Ok(Box::new(build(descriptor)) as Box<dyn Constraint>)
+            }),
+        );
+    }
+
+    /// Registers a built-in constraint under the name of its descriptor variant.
+    fn register_builtin<D>(&mut self, name: &str, variant: fn(D) -> ConstraintDescriptor)
+    where
+        D: DeserializeOwned + 'static,
+    {
+        self.factories.insert(
+            name.to_string(),
+            Box::new(move |parameters| {
+                let descriptor = parameters.into_rust::<D>()?;
+                Ok(variant(descriptor).into())
+            }),
+        );
+        self.builtins.insert(
+            name.to_string(),
+            Box::new(move |parameters| parameters.into_rust::<D>().map(variant)),
+        );
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.factories.contains_key(name)
+    }
+
+    /// The descriptor variant for a built-in constraint referred to by name, or `None` if `name`
+    /// isn't a built-in constraint, or has been replaced by another.
+    pub fn builtin(
+        &self,
+        name: &str,
+        parameters: &RawValue,
+    ) -> Option<Result<ConstraintDescriptor, RegistryError>> {
+        let parse = self.builtins.get(name)?;
+        Some(parse(parameters).map_err(|err| RegistryError::Parameters {
+            name: name.to_string(),
+            err: Box::new(err),
+        }))
+    }
+
+    pub fn build(
+        &self,
+        name: &str,
+        parameters: &RawValue,
+    ) -> Result<Box<dyn Constraint>, RegistryError> {
+        let factory = self
+            .factories
+            .get(name)
+            .ok_or_else(|| RegistryError::Unknown(name.to_string()))?;
+        factory(parameters).map_err(|err| RegistryError::Parameters {
+            name: name.to_string(),
+            err: Box::new(err),
+        })
+    }
+}
+
+impl Default for ConstraintRegistry {
+    /// A registry with every built-in constraint, named after its descriptor variant.
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register_builtin("Distance", ConstraintDescriptor::Distance);
+        registry.register_builtin("Angle", ConstraintDescriptor::Angle);
+        registry.register_builtin("Fabrik", ConstraintDescriptor::Fabrik);
+        registry.register_builtin("Collision", ConstraintDescriptor::Collision);
+        registry.register_builtin("Obstacle", ConstraintDescriptor::Obstacle);
+        registry.register_builtin("Gait", ConstraintDescriptor::Gait);
+        registry.register_builtin("Spring", ConstraintDescriptor::Spring);
+        registry.register_builtin("Pin", ConstraintDescriptor::Pin);
+        registry
+    }
+}
+
+impl fmt::Debug for ConstraintRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.factories.keys()).finish()
+    }
+}
+
+/// The registry used when building bodies from descriptors, starting with the built-in
+/// constraints.
+pub fn registry() -> &'static RwLock<ConstraintRegistry> {
+    static REGISTRY: OnceLock<RwLock<ConstraintRegistry>> = OnceLock::new();
+    REGISTRY.get_or_init(Default::default)
+}
+
+/// Registers a constraint with the global [`registry`], so that it can be used in descriptors.
+pub fn register_constraint<D, C>(
+    name: impl Into<String>,
+    build: impl Fn(D) -> C + Send + Sync + 'static,
+) where
+    D: DeserializeOwned,
+    C: Constraint + 'static,
+{
+    registry()
+        .write()
+        .unwrap_or_else(|err| err.into_inner())
+        .register(name, build);
+}
+
+/// Builds a constraint registered with the global [`registry`].
+pub fn build_constraint(
+    name: &str,
+    parameters: &RawValue,
+) -> Result<Box<dyn Constraint>, RegistryError> {
+    registry()
+        .read()
+        .unwrap_or_else(|err| err.into_inner())
+        .build(name, parameters)
+}
+
+/// The descriptor variant for a built-in constraint registered with the global [`registry`], as
+/// with [`ConstraintRegistry::builtin`].
+pub fn builtin_constraint(
+    name: &str,
+    parameters: &RawValue,
+) -> Option<Result<ConstraintDescriptor, RegistryError>> {
+    registry()
+        .read()
+        .unwrap_or_else(|err| err.into_inner())
+        .builtin(name, parameters)
+}
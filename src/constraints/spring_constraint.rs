@@ -0,0 +1,112 @@
+use crate::{body::AttachmentPoint, joint::Joint};
+
+use super::Constraint;
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A damped spring between two neighbouring joints.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Spring {
+    /// The length at which the spring doesn't push or pull.
+    pub rest_length: f32,
+    /// How hard the spring pulls back per unit it is stretched. Zero turns the spring off.
+    pub stiffness: f32,
+    /// How hard the spring resists the joints moving apart or together, per unit of speed.
+    pub damping: f32,
+}
+
+impl Default for Spring {
+    fn default() -> Self {
+        Self {
+            rest_length: 20.,
+            stiffness: 400.,
+            damping: 10.,
+        }
+    }
+}
+
+/// Joins each pair of neighbouring joints with a spring, so the body can stretch. Give the joints
+/// [`Inertia`](crate::joint::Inertia) as well to make it wobble. The first joint is led by
+/// whatever moves the body, so it is never pulled on.
+#[derive(Clone, Debug, Default)]
+pub struct SpringConstraint {
+    /// The spring for each segment, from the first joint backwards.
+    pub(crate) springs: Vec<Spring>,
+    /// Where each joint was after the last step, used to damp the springs.
+    previous_positions: Vec<Vec2>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpringConstraintDescriptor {
+    /// The spring for each segment, from the first joint backwards. Segments past the end of the
+    /// list use its last entry.
+    pub springs: Vec<Spring>,
+}
+
+impl Default for SpringConstraintDescriptor {
+    fn default() -> Self {
+        Self {
+            springs: vec![Spring::default()],
+        }
+    }
+}
+
+impl SpringConstraint {
+    pub fn new(descriptor: SpringConstraintDescriptor) -> Self {
+        let SpringConstraintDescriptor { springs } = descriptor;
+
+        Self {
+            springs,
+            previous_positions: Vec::new(),
+        }
+    }
+}
+
+impl Constraint for SpringConstraint {
+    fn apply(
+        &mut self,
+        joints: &mut Vec<Joint>,
+        _attachment_point: Option<AttachmentPoint>,
+        dt: f32,
+    ) {
+        let Some(last_spring) = self.springs.last() else {
+            return;
+        };
+        if self.previous_positions.len() != joints.len() {
+            self.previous_positions = joints.iter().map(|joint| joint.pos).collect();
+        }
+
+        // Each spring is solved as a compliant distance constraint, which stays stable however
+        // stiff it is, unlike integrating the spring force directly.
+        for i in 0..joints.len().saturating_sub(1) {
+            let spring = self.springs.get(i).unwrap_or(last_spring);
+            if spring.stiffness <= 0. || dt <= 0. {
+                continue;
+            }
+
+            let delta = joints[i + 1].pos - joints[i].pos;
+            let direction = delta.normalize_or(Vec2::X);
+            let stretch = delta.length() - spring.rest_length;
+            let motion = (joints[i + 1].pos
+                - self.previous_positions[i + 1]
+                - (joints[i].pos - self.previous_positions[i]))
+                .dot(direction);
+
+            let compliance = 1. / (spring.stiffness * dt * dt);
+            let damping = spring.damping / (spring.stiffness * dt);
+            // The first joint can't be pulled, so the next one takes all of the correction.
+            let weight = if i == 0 { 0. } else { 1. };
+            let correction =
+                (-stretch - damping * motion) / ((1. + damping) * (weight + 1.) + compliance);
+
+            joints[i].pos -= weight * correction * direction;
+            joints[i + 1].pos += correction * direction;
+        }
+
+        for (previous, joint) in self.previous_positions.iter_mut().zip(joints.iter()) {
+            *previous = joint.pos;
+        }
+    }
+}
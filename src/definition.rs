@@ -0,0 +1,412 @@
+//! Loading of creature definitions from [RON](https://github.com/ron-rs/ron) files.
+//!
+//! A definition is a serialized [`BodyDescriptor`] tree, for example:
+//!
+//! ```ron
+//! (
+//!     fill_color: (0.38, 0.65, 0.72, 1.0),
+//!     joints: [(radius: 20.0), (radius: 15.0), (radius: 10.0)],
+//!     constraints: [Distance((distance: 25.0))],
+//! )
+//! ```
+//!
+//! Fields which are left out take their default value.
+
+use std::{collections::HashSet, f32::consts::PI, fmt, fs, io, path::Path};
+
+use crate::{
+    body::{Body, BodyDescriptor, MAX_OUTLINE_SUBDIVISIONS},
+    constraints::{
+        build_constraint, builtin_constraint, ConstraintDescriptor, PinTarget, RegistryError,
+        MAX_OVERSHOOT,
+    },
+    obstacle::Obstacle,
+};
+
+#[derive(Debug)]
+pub enum DefinitionError {
+    Io(io::Error),
+    Parse(ron::error::SpannedError),
+    Serialize(ron::Error),
+    /// The definition is valid, but one of its custom constraints couldn't be built, e.g. because
+    /// it was unregistered in the meantime.
+    Constraint(RegistryError),
+    /// The definition parsed, but describes a body which can't be simulated.
+    Invalid {
+        /// Where in the tree the problem is, e.g. `joints[0].bodies[1].constraints[0]`.
+        location: String,
+        reason: String,
+    },
+}
+
+impl fmt::Display for DefinitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DefinitionError::Io(err) => write!(f, "could not access definition: {err}"),
+            DefinitionError::Parse(err) => write!(f, "could not parse definition: {err}"),
+            DefinitionError::Serialize(err) => write!(f, "could not serialize definition: {err}"),
+            DefinitionError::Constraint(err) => write!(f, "could not build constraint: {err}"),
+            DefinitionError::Invalid { location, reason } => {
+                write!(f, "invalid definition at `{location}`: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DefinitionError {}
+
+/// Parses and validates a body definition.
+pub fn parse(source: &str) -> Result<BodyDescriptor, DefinitionError> {
+    let descriptor = ron::from_str(source).map_err(DefinitionError::Parse)?;
+    validate(&descriptor)?;
+    Ok(descriptor)
+}
+
+/// Reads a body definition from a file and builds the body it describes.
+pub fn load(path: impl AsRef<Path>) -> Result<Body, DefinitionError> {
+    let source = fs::read_to_string(path).map_err(DefinitionError::Io)?;
+    Body::try_new(parse(&source)?).map_err(DefinitionError::Constraint)
+}
+
+/// Serializes a body definition, such that it can be read back with [`parse`].
+pub fn to_string(descriptor: &BodyDescriptor) -> Result<String, DefinitionError> {
+    ron::ser::to_string_pretty(descriptor, ron::ser::PrettyConfig::default())
+        .map_err(DefinitionError::Serialize)
+}
+
+/// Checks that a body descriptor, and everything nested in it, can be simulated.
+pub fn validate(descriptor: &BodyDescriptor) -> Result<(), DefinitionError> {
+    let mut names = HashSet::new();
+    collect_joint_names(descriptor, "body", &mut names)?;
+    validate_body(descriptor, "body", true, &names)
+}
+
+/// Gathers the names of every joint in the tree, which must be unique.
+fn collect_joint_names<'a>(
+    descriptor: &'a BodyDescriptor,
+    location: &str,
+    names: &mut HashSet<&'a str>,
+) -> Result<(), DefinitionError> {
+    for (i, joint) in descriptor.joints.iter().enumerate() {
+        let location = format!("{location}.joints[{i}]");
+        if let Some(name) = &joint.name {
+            if !names.insert(name) {
+                return Err(DefinitionError::Invalid {
+                    location: format!("{location}.name"),
+                    reason: format!("the name `{name}` is used by more than one joint"),
+                });
+            }
+        }
+
+        for (j, body) in joint.bodies.iter().enumerate() {
+            collect_joint_names(body, &format!("{location}.bodies[{j}]"), names)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_body(
+    descriptor: &BodyDescriptor,
+    location: &str,
+    root: bool,
+    names: &HashSet<&str>,
+) -> Result<(), DefinitionError> {
+    let invalid = |location: String, reason: &str| DefinitionError::Invalid {
+        location,
+        reason: reason.to_string(),
+    };
+
+    if descriptor.joints.is_empty() {
+        return Err(invalid(
+            location.to_string(),
+            "a body needs at least one joint",
+        ));
+    }
+
+    if !is_non_negative(descriptor.line_thickness) {
+        return Err(invalid(
+            format!("{location}.line_thickness"),
+            "must be a non-negative number",
+        ));
+    }
+
+    if descriptor.outline_subdivisions > MAX_OUTLINE_SUBDIVISIONS {
+        return Err(invalid(
+            format!("{location}.outline_subdivisions"),
+            &format!("must be at most {MAX_OUTLINE_SUBDIVISIONS}"),
+        ));
+    }
+
+    if !descriptor.attachment_angle.is_finite() || !descriptor.attachment_offset.is_finite() {
+        return Err(invalid(
+            location.to_string(),
+            "the attachment angle and offset must be finite",
+        ));
+    }
+
+    for (i, constraint) in descriptor.constraints.iter().enumerate() {
+        let location = format!("{location}.constraints[{i}]");
+        validate_constraint(constraint, location, descriptor, root, names)?;
+    }
+
+    for (i, joint) in descriptor.joints.iter().enumerate() {
+        let location = format!("{location}.joints[{i}]");
+        if !is_positive(joint.radius) {
+            return Err(invalid(format!("{location}.radius"), "must be positive"));
+        }
+
+        if let Some(inertia) = &joint.inertia {
+            if !is_non_negative(inertia.damping_half_life) || !inertia.gravity.is_finite() {
+                return Err(invalid(
+                    format!("{location}.inertia"),
+                    "the damping half-life can't be negative and the gravity must be finite",
+                ));
+            }
+        }
+
+        for (j, body) in joint.bodies.iter().enumerate() {
+            validate_body(body, &format!("{location}.bodies[{j}]"), false, names)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks a constraint of the body described by `descriptor`, which is at `location`.
+fn validate_constraint(
+    constraint: &ConstraintDescriptor,
+    location: String,
+    descriptor: &BodyDescriptor,
+    root: bool,
+    names: &HashSet<&str>,
+) -> Result<(), DefinitionError> {
+    let invalid = |location: String, reason: &str| DefinitionError::Invalid {
+        location,
+        reason: reason.to_string(),
+    };
+
+    match constraint {
+        ConstraintDescriptor::Distance(distance) => {
+            if !is_positive(distance.distance) {
+                return Err(invalid(location, "the distance must be positive"));
+            }
+            if !distance.distances.iter().copied().all(is_positive) {
+                return Err(invalid(location, "every segment distance must be positive"));
+            }
+            if !is_non_negative(distance.half_life) {
+                return Err(invalid(location, "the half-life can't be negative"));
+            }
+        }
+        ConstraintDescriptor::Angle(angle) => {
+            if !angle.angle.is_finite() {
+                return Err(invalid(location, "the angle must be finite"));
+            }
+            for (j, range) in angle.ranges.iter().enumerate() {
+                let within = |value: f32| (0. ..=PI).contains(&value);
+                if !within(range.min) || !within(range.max) || range.max < range.min {
+                    return Err(invalid(
+                        format!("{location}.ranges[{j}]"),
+                        "the minimum and maximum must be between 0 and PI, in order",
+                    ));
+                }
+                if range
+                    .rest
+                    .is_some_and(|rest| !(range.min..=range.max).contains(&rest))
+                    || !is_non_negative(range.rest_half_life)
+                {
+                    return Err(invalid(
+                        format!("{location}.ranges[{j}]"),
+                        "the rest angle must be within the range, with a non-negative half-life",
+                    ));
+                }
+            }
+            if !is_non_negative(angle.half_life) {
+                return Err(invalid(location, "the half-life can't be negative"));
+            }
+        }
+        ConstraintDescriptor::Fabrik(fabrik) => {
+            if root {
+                return Err(invalid(
+                    location,
+                    "fabrik constraints need an attachment point, so can't be used on the root body",
+                ));
+            }
+            if !is_positive(fabrik.joint_distance) {
+                return Err(invalid(location, "the joint distance must be positive"));
+            }
+            if !is_non_negative(fabrik.half_life) {
+                return Err(invalid(location, "the half-life can't be negative"));
+            }
+            if !is_non_negative(fabrik.step_duration) {
+                return Err(invalid(location, "the step duration can't be negative"));
+            }
+            if !fabrik.step_height.is_finite() {
+                return Err(invalid(location, "the step height must be finite"));
+            }
+            if !(0. ..=MAX_OVERSHOOT).contains(&fabrik.overshoot) {
+                return Err(invalid(
+                    location,
+                    &format!("the overshoot must be between 0 and {MAX_OVERSHOOT}"),
+                ));
+            }
+            for (j, limit) in fabrik.joint_limits.iter().enumerate() {
+                if !is_non_negative(limit.min_angle)
+                    || !is_non_negative(limit.max_angle)
+                    || limit.max_angle < limit.min_angle
+                {
+                    return Err(invalid(
+                        format!("{location}.joint_limits[{j}]"),
+                        "the angles must be non-negative, with the minimum no larger than the maximum",
+                    ));
+                }
+            }
+        }
+        ConstraintDescriptor::Collision(collision) => {
+            if !is_non_negative(collision.half_life) {
+                return Err(invalid(location, "the half-life can't be negative"));
+            }
+        }
+        ConstraintDescriptor::Obstacle(obstacle) => {
+            if !is_non_negative(obstacle.half_life) {
+                return Err(invalid(location, "the half-life can't be negative"));
+            }
+            for (j, obstacle) in obstacle.obstacles.iter().enumerate() {
+                let location = format!("{location}.obstacles[{j}]");
+                match obstacle {
+                    Obstacle::Circle { center, radius } => {
+                        if !center.is_finite() || !is_positive(*radius) {
+                            return Err(invalid(
+                                location,
+                                "the centre must be finite and the radius positive",
+                            ));
+                        }
+                    }
+                    Obstacle::Rect { min, max } => {
+                        if !min.is_finite() || !max.is_finite() || min.cmpgt(*max).any() {
+                            return Err(invalid(
+                                location,
+                                "the corners must be finite, with the minimum no larger than the maximum",
+                            ));
+                        }
+                    }
+                    Obstacle::Polygon { points } => {
+                        if points.len() < 3 || !points.iter().all(|point| point.is_finite()) {
+                            return Err(invalid(
+                                location,
+                                "a polygon needs at least three points, all of them finite",
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+        ConstraintDescriptor::Spring(spring) => {
+            for (j, spring) in spring.springs.iter().enumerate() {
+                if !is_positive(spring.rest_length)
+                    || !is_non_negative(spring.stiffness)
+                    || !is_non_negative(spring.damping)
+                {
+                    return Err(invalid(
+                        format!("{location}.springs[{j}]"),
+                        "the rest length must be positive, and the stiffness and damping non-negative",
+                    ));
+                }
+            }
+        }
+        ConstraintDescriptor::Pin(pin) => {
+            if pin.joint >= descriptor.joints.len() {
+                return Err(invalid(location, "the pinned joint doesn't exist"));
+            }
+            if !is_non_negative(pin.distance) || !is_non_negative(pin.half_life) {
+                return Err(invalid(
+                    location,
+                    "the distance and half-life can't be negative",
+                ));
+            }
+            match &pin.target {
+                PinTarget::Position(position) if !position.is_finite() => {
+                    return Err(invalid(location, "the position must be finite"));
+                }
+                PinTarget::Joint(name) if !names.contains(name.as_str()) => {
+                    return Err(invalid(
+                        location,
+                        &format!("there is no joint named `{name}`"),
+                    ));
+                }
+                _ => {}
+            }
+        }
+        ConstraintDescriptor::Custom { name, parameters } => {
+            match builtin_constraint(name, parameters) {
+                // Built-in constraints named through `Custom` get the same checks as when they're
+                // named directly.
+                Some(Ok(builtin)) => {
+                    return validate_constraint(&builtin, location, descriptor, root, names);
+                }
+                Some(Err(err)) => return Err(invalid(location, &err.to_string())),
+                None => {
+                    if let Err(err) = build_constraint(name, parameters) {
+                        return Err(invalid(location, &err.to_string()));
+                    }
+                }
+            }
+        }
+        ConstraintDescriptor::Gait(gait) => {
+            if !is_non_negative(gait.step_duration) {
+                return Err(invalid(location, "the step duration can't be negative"));
+            }
+
+            let pairs = leg_pairs(descriptor);
+            if pairs.iter().any(Option::is_some) && pairs.iter().any(Option::is_none) {
+                return Err(invalid(
+                    location,
+                    "either every leg or none of them must say which pair it belongs to",
+                ));
+            }
+            if let Some(pair) = pairs
+                .iter()
+                .flatten()
+                .find(|pair| pairs.iter().flatten().filter(|other| other == pair).count() > 2)
+            {
+                return Err(invalid(
+                    location,
+                    &format!("more than two legs belong to pair {pair}"),
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The pair each leg attached to the body belongs to, in the order a gait constraint finds them.
+fn leg_pairs(descriptor: &BodyDescriptor) -> Vec<Option<usize>> {
+    descriptor
+        .joints
+        .iter()
+        .flat_map(|joint| &joint.bodies)
+        .filter_map(|body| {
+            body.constraints
+                .iter()
+                .find_map(|constraint| match constraint {
+                    ConstraintDescriptor::Fabrik(fabrik) => Some(fabrik.pair),
+                    ConstraintDescriptor::Custom { name, parameters } => {
+                        match builtin_constraint(name, parameters) {
+                            Some(Ok(ConstraintDescriptor::Fabrik(fabrik))) => Some(fabrik.pair),
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                })
+        })
+        .collect()
+}
+
+fn is_positive(value: f32) -> bool {
+    value.is_finite() && value > 0.
+}
+
+fn is_non_negative(value: f32) -> bool {
+    value.is_finite() && value >= 0.
+}
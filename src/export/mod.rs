@@ -0,0 +1,5 @@
+//! Exporting of bodies to files, without needing a window.
+
+pub mod raster;
+pub mod recording;
+pub mod svg;
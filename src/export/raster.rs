@@ -0,0 +1,274 @@
+use std::{fs::File, io::BufWriter, path::Path};
+
+use macroquad::{
+    color::Color,
+    math::{Rect, Vec2},
+};
+
+use crate::body::Body;
+
+/// Space left around the body by [`snapshot`], in world units.
+const MARGIN: f32 = 20.0;
+
+/// An RGBA image rendered on the CPU. Shapes are drawn at `samples` times the resolution in each
+/// direction and averaged down when read, which smooths out the edges.
+#[derive(Clone, Debug)]
+pub struct Canvas {
+    width: u32,
+    height: u32,
+    samples: u32,
+    /// The world-space area which is mapped onto the canvas.
+    view: Rect,
+    buffer: Vec<Color>,
+}
+
+impl Canvas {
+    pub fn new(width: u32, height: u32, view: Rect, background: Color) -> Self {
+        Self::with_samples(width, height, view, background, 2)
+    }
+
+    pub fn with_samples(
+        width: u32,
+        height: u32,
+        view: Rect,
+        background: Color,
+        samples: u32,
+    ) -> Self {
+        let samples = samples.max(1);
+        let size = (width * samples) as usize * (height * samples) as usize;
+        Self {
+            width,
+            height,
+            samples,
+            view,
+            buffer: vec![background; size],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Draws the body, and all of its inner bodies, in the same way as [`Body::draw`].
+    pub fn draw_body(&mut self, body: &Body) {
+        body.visit_in_draw_order(&mut |body| self.draw_shape(body));
+    }
+
+    fn draw_shape(&mut self, body: &Body) {
+        let points = body.points();
+
+        let stroke = body.stroke(&points);
+        for triangle in stroke.indices.chunks_exact(3) {
+            self.fill_triangle(
+                stroke.vertices[triangle[0] as usize],
+                stroke.vertices[triangle[1] as usize],
+                stroke.vertices[triangle[2] as usize],
+                body.line_color,
+            );
+        }
+
+        for triangle in Body::fill_indices(points.len()).chunks_exact(3) {
+            self.fill_triangle(
+                points[triangle[0] as usize],
+                points[triangle[1] as usize],
+                points[triangle[2] as usize],
+                body.fill_color,
+            );
+        }
+    }
+
+    pub fn fill_triangle(&mut self, a: Vec2, b: Vec2, c: Vec2, color: Color) {
+        let (a, b, c) = (self.to_buffer(a), self.to_buffer(b), self.to_buffer(c));
+        let area = (b - a).perp_dot(c - a);
+        if area == 0. {
+            return;
+        }
+
+        // Flip the edge tests for clockwise triangles, so either winding is filled.
+        let sign = area.signum();
+        self.fill_samples(
+            a.min(b).min(c),
+            a.max(b).max(c),
+            |sample| {
+                sign * (b - a).perp_dot(sample - a) >= 0.
+                    && sign * (c - b).perp_dot(sample - b) >= 0.
+                    && sign * (a - c).perp_dot(sample - c) >= 0.
+            },
+            color,
+        );
+    }
+
+    /// Blends `color` over every sample within the given buffer-space bounds which passes `inside`.
+    fn fill_samples(&mut self, min: Vec2, max: Vec2, inside: impl Fn(Vec2) -> bool, color: Color) {
+        let buffer_width = self.width * self.samples;
+        let buffer_height = self.height * self.samples;
+        let min_x = min.x.floor().max(0.) as u32;
+        let min_y = min.y.floor().max(0.) as u32;
+        let max_x = (max.x.ceil().max(0.) as u32).min(buffer_width);
+        let max_y = (max.y.ceil().max(0.) as u32).min(buffer_height);
+
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                if inside(Vec2::new(x as f32 + 0.5, y as f32 + 0.5)) {
+                    let sample = &mut self.buffer[(y * buffer_width + x) as usize];
+                    *sample = blend(*sample, color);
+                }
+            }
+        }
+    }
+
+    fn scale(&self) -> Vec2 {
+        Vec2::new(
+            (self.width * self.samples) as f32 / self.view.w,
+            (self.height * self.samples) as f32 / self.view.h,
+        )
+    }
+
+    fn to_buffer(&self, point: Vec2) -> Vec2 {
+        (point - self.view.point()) * self.scale()
+    }
+
+    /// The averaged image, as 8-bit RGBA rows.
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        let buffer_width = self.width * self.samples;
+        let sample_count = (self.samples * self.samples) as f32;
+        let mut pixels = Vec::with_capacity((self.width * self.height * 4) as usize);
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let mut sum = [0.; 4];
+                for sy in 0..self.samples {
+                    for sx in 0..self.samples {
+                        let index = (y * self.samples + sy) * buffer_width + x * self.samples + sx;
+                        let sample = self.buffer[index as usize];
+                        sum[0] += sample.r;
+                        sum[1] += sample.g;
+                        sum[2] += sample.b;
+                        sum[3] += sample.a;
+                    }
+                }
+                pixels.extend(
+                    sum.map(|channel| {
+                        ((channel / sample_count).clamp(0., 1.) * 255.).round() as u8
+                    }),
+                );
+            }
+        }
+
+        pixels
+    }
+
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<(), png::EncodingError> {
+        let file = File::create(path)?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.to_rgba8())?;
+        writer.finish()
+    }
+}
+
+/// Renders the body onto a canvas of the given size, framed to fit the whole body.
+pub fn snapshot(body: &Body, width: u32, height: u32, background: Color) -> Canvas {
+    let mut canvas = Canvas::new(width, height, fit([body], width, height), background);
+    canvas.draw_body(body);
+    canvas
+}
+
+/// The smallest view with the canvas' aspect ratio which contains all of the given bodies.
+pub fn fit<'a>(bodies: impl IntoIterator<Item = &'a Body>, width: u32, height: u32) -> Rect {
+    let mut min = Vec2::splat(f32::INFINITY);
+    let mut max = Vec2::splat(f32::NEG_INFINITY);
+    for body in bodies {
+        body.visit_in_draw_order(&mut |body| {
+            let stroke = body.line_thickness.max(0.) / 2.;
+            for point in body.points() {
+                min = min.min(point - stroke);
+                max = max.max(point + stroke);
+            }
+        });
+    }
+
+    if !min.is_finite() || !max.is_finite() {
+        min = Vec2::ZERO;
+        max = Vec2::ZERO;
+    }
+
+    let center = (min + max) / 2.;
+    let mut size = max - min + 2. * MARGIN;
+    let aspect = width as f32 / height.max(1) as f32;
+    if size.x / size.y < aspect {
+        size.x = size.y * aspect;
+    } else {
+        size.y = size.x / aspect;
+    }
+
+    Rect::new(
+        center.x - size.x / 2.,
+        center.y - size.y / 2.,
+        size.x,
+        size.y,
+    )
+}
+
+/// Blends `source` over `destination`.
+fn blend(destination: Color, source: Color) -> Color {
+    let a = source.a + destination.a * (1. - source.a);
+    if a == 0. {
+        return Color::new(0., 0., 0., 0.);
+    }
+
+    let mix = |s: f32, d: f32| (s * source.a + d * destination.a * (1. - source.a)) / a;
+    Color::new(
+        mix(source.r, destination.r),
+        mix(source.g, destination.g),
+        mix(source.b, destination.b),
+        a,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use macroquad::color::BLACK;
+
+    use super::*;
+    use crate::{body::BodyDescriptor, joint::JointDescriptor};
+
+    const FILL: Color = Color::new(1., 0., 0., 1.);
+    const BACKGROUND: Color = Color::new(0., 0., 1., 1.);
+
+    fn pixel(canvas: &Canvas, x: u32, y: u32) -> [u8; 4] {
+        let index = ((y * canvas.width() + x) * 4) as usize;
+        canvas.to_rgba8()[index..index + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn snapshot_fills_body_over_background() {
+        let mut body = Body::new(BodyDescriptor {
+            line_color: BLACK,
+            line_thickness: 4.,
+            fill_color: FILL,
+            joints: vec![
+                JointDescriptor {
+                    radius: 20.,
+                    ..Default::default()
+                };
+                3
+            ],
+            ..Default::default()
+        });
+        for (i, joint) in body.joints.iter_mut().enumerate() {
+            joint.pos = Vec2::new(i as f32 * 15., 0.);
+        }
+
+        let canvas = snapshot(&body, 64, 48, BACKGROUND);
+        assert_eq!(pixel(&canvas, 32, 24), [255, 0, 0, 255]);
+        assert_eq!(pixel(&canvas, 0, 0), [0, 0, 255, 255]);
+        assert_eq!(pixel(&canvas, 63, 47), [0, 0, 255, 255]);
+    }
+}
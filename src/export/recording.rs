@@ -0,0 +1,190 @@
+use std::{fmt, fs::File, io, io::BufWriter, path::Path};
+
+use macroquad::{color::Color, math::Vec2};
+
+use crate::{
+    body::Body,
+    constants::{BACKGROUND_COLOR, FIXED_TIMESTEP, HEAD_HALF_LIFE},
+    path,
+};
+
+use super::raster::{self, Canvas};
+
+/// Steps a body forward at a fixed timestep and captures the frames as an animated clip.
+#[derive(Clone, Debug)]
+pub struct Recorder {
+    /// The number of frames to capture.
+    pub frames: u32,
+    /// The number of simulation steps between each captured frame.
+    pub steps_per_frame: u32,
+    /// The duration of a single simulation step, in seconds.
+    pub timestep: f32,
+    pub width: u32,
+    pub height: u32,
+    pub background: Color,
+    /// The time, in seconds, taken for the head to cover half of the distance to the path.
+    pub head_half_life: f32,
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Self {
+            frames: 120,
+            steps_per_frame: 2,
+            timestep: FIXED_TIMESTEP,
+            width: 320,
+            height: 320,
+            background: BACKGROUND_COLOR,
+            head_half_life: HEAD_HALF_LIFE,
+        }
+    }
+}
+
+impl Recorder {
+    /// Records the body with its head following `path`, which maps the time in seconds since the
+    /// recording started to a world position.
+    pub fn record_path(&self, body: &mut Body, mut path: impl FnMut(f32) -> Vec2) -> Clip {
+        let half_life = self.head_half_life;
+        self.record(body, |body, time, dt| {
+            path::follow(body, path(time), half_life, dt);
+        })
+    }
+
+    /// Records the body, calling `drive` with the elapsed time and timestep before every
+    /// simulation step, so it can move the body however it likes.
+    pub fn record(&self, body: &mut Body, mut drive: impl FnMut(&mut Body, f32, f32)) -> Clip {
+        let dt = self.timestep;
+        let mut time = 0.;
+        let mut states = Vec::with_capacity(self.frames as usize);
+        for _ in 0..self.frames {
+            for _ in 0..self.steps_per_frame {
+                time += dt;
+                drive(body, time, dt);
+                body.apply_constraints(None, dt);
+            }
+            states.push(body.clone());
+        }
+
+        // Frame the whole clip the same way, so the camera doesn't jump around.
+        let view = raster::fit(&states, self.width, self.height);
+        let frames = states
+            .iter()
+            .map(|state| {
+                let mut canvas = Canvas::new(self.width, self.height, view, self.background);
+                canvas.draw_body(state);
+                canvas.to_rgba8()
+            })
+            .collect();
+
+        Clip {
+            width: self.width,
+            height: self.height,
+            frame_duration: dt * self.steps_per_frame as f32,
+            frames,
+        }
+    }
+}
+
+/// A sequence of RGBA frames, all of the same size.
+#[derive(Clone, Debug)]
+pub struct Clip {
+    pub width: u32,
+    pub height: u32,
+    /// How long each frame is shown for, in seconds.
+    pub frame_duration: f32,
+    pub frames: Vec<Vec<u8>>,
+}
+
+#[derive(Debug)]
+pub enum RecordingError {
+    Io(io::Error),
+    Gif(gif::EncodingError),
+    Png(png::EncodingError),
+    /// GIFs can only be up to 65535 pixels in either direction.
+    TooLarge,
+    Empty,
+}
+
+impl fmt::Display for RecordingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordingError::Io(err) => write!(f, "could not write recording: {err}"),
+            RecordingError::Gif(err) => write!(f, "could not encode GIF: {err}"),
+            RecordingError::Png(err) => write!(f, "could not encode APNG: {err}"),
+            RecordingError::TooLarge => write!(f, "the recording is too large for a GIF"),
+            RecordingError::Empty => write!(f, "the recording has no frames"),
+        }
+    }
+}
+
+impl std::error::Error for RecordingError {}
+
+impl Clip {
+    /// Saves the clip as an APNG if the path ends in `.png` or `.apng`, or as a GIF otherwise.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), RecordingError> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("png" | "apng") => self.save_apng(path),
+            _ => self.save_gif(path),
+        }
+    }
+
+    pub fn save_gif(&self, path: impl AsRef<Path>) -> Result<(), RecordingError> {
+        if self.frames.is_empty() {
+            return Err(RecordingError::Empty);
+        }
+        let (Ok(width), Ok(height)) = (u16::try_from(self.width), u16::try_from(self.height))
+        else {
+            return Err(RecordingError::TooLarge);
+        };
+
+        let file = File::create(path).map_err(RecordingError::Io)?;
+        let mut encoder = gif::Encoder::new(BufWriter::new(file), width, height, &[])
+            .map_err(RecordingError::Gif)?;
+        encoder
+            .set_repeat(gif::Repeat::Infinite)
+            .map_err(RecordingError::Gif)?;
+
+        // GIF delays are in hundredths of a second.
+        let delay = (self.frame_duration * 100.).round().max(1.) as u16;
+        for frame in &self.frames {
+            let mut pixels = frame.clone();
+            let mut frame = gif::Frame::from_rgba_speed(width, height, &mut pixels, 10);
+            frame.delay = delay;
+            encoder.write_frame(&frame).map_err(RecordingError::Gif)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn save_apng(&self, path: impl AsRef<Path>) -> Result<(), RecordingError> {
+        if self.frames.is_empty() {
+            return Err(RecordingError::Empty);
+        }
+
+        let file = File::create(path).map_err(RecordingError::Io)?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .set_animated(self.frames.len() as u32, 0)
+            .map_err(RecordingError::Png)?;
+        // APNG delays are a fraction of a second, so use milliseconds.
+        let delay = (self.frame_duration * 1000.).round() as u16;
+        encoder
+            .set_frame_delay(delay, 1000)
+            .map_err(RecordingError::Png)?;
+
+        let mut writer = encoder.write_header().map_err(RecordingError::Png)?;
+        for frame in &self.frames {
+            writer
+                .write_image_data(frame)
+                .map_err(RecordingError::Png)?;
+        }
+        writer.finish().map_err(RecordingError::Png)
+    }
+}
@@ -0,0 +1,84 @@
+use std::{fmt::Write, fs, io, path::Path};
+
+use macroquad::{color::Color, math::Vec2};
+
+use crate::{body::Body, stroke::LineJoin};
+
+/// Space left around the body, in world units.
+const MARGIN: f32 = 20.0;
+
+/// Renders the body, and all of its inner bodies, to an SVG document. Bodies are written in the
+/// same order as [`Body::draw`], so the result looks the same as the current frame.
+pub fn to_string(body: &Body) -> String {
+    let mut paths = String::new();
+    let mut min = Vec2::splat(f32::INFINITY);
+    let mut max = Vec2::splat(f32::NEG_INFINITY);
+
+    body.visit_in_draw_order(&mut |body| {
+        let outline = body.outline();
+        if outline.is_empty() {
+            return;
+        }
+
+        let stroke = body.line_thickness.max(0.) / 2.;
+        for point in &outline {
+            min = min.min(*point - stroke);
+            max = max.max(*point + stroke);
+        }
+
+        let d = path_data(&outline);
+        if body.line_thickness > 0. {
+            let _ = writeln!(
+                paths,
+                r#"  <path d="{d}" fill="none" stroke="{}" stroke-opacity="{}" stroke-width="{}" stroke-linejoin="{}" stroke-miterlimit="4"/>"#,
+                rgb(body.line_color),
+                body.line_color.a,
+                body.line_thickness,
+                match body.line_join {
+                    LineJoin::Miter => "miter",
+                    LineJoin::Round => "round",
+                },
+            );
+        }
+        let _ = writeln!(
+            paths,
+            r#"  <path d="{d}" fill="{}" fill-opacity="{}"/>"#,
+            rgb(body.fill_color),
+            body.fill_color.a,
+        );
+    });
+
+    if !min.is_finite() || !max.is_finite() {
+        min = Vec2::ZERO;
+        max = Vec2::ZERO;
+    }
+    let origin = min - MARGIN;
+    let size = max - min + 2. * MARGIN;
+
+    format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{:.2} {:.2} {:.2} {:.2}" width="{:.0}" height="{:.0}">
+{paths}</svg>
+"#,
+        origin.x, origin.y, size.x, size.y, size.x, size.y,
+    )
+}
+
+/// Writes the body, and all of its inner bodies, to an SVG file.
+pub fn save(body: &Body, path: impl AsRef<Path>) -> io::Result<()> {
+    fs::write(path, to_string(body))
+}
+
+fn path_data(outline: &[Vec2]) -> String {
+    let mut d = String::new();
+    for (i, point) in outline.iter().enumerate() {
+        let command = if i == 0 { 'M' } else { 'L' };
+        let _ = write!(d, "{command}{:.2} {:.2} ", point.x, point.y);
+    }
+    d.push('Z');
+    d
+}
+
+fn rgb(color: Color) -> String {
+    let [r, g, b, _]: [u8; 4] = color.into();
+    format!("rgb({r},{g},{b})")
+}
@@ -1,5 +1,7 @@
+use macroquad::color::Color;
+#[cfg(feature = "render")]
 use macroquad::{
-    color::{Color, GRAY},
+    color::GRAY,
     math::Vec2,
     ui::{hash, Id, Ui},
 };
@@ -39,14 +41,18 @@ impl ColorExtension for Color {
     }
 }
 
+#[cfg(feature = "render")]
 pub const UI_WIDTH: f32 = 250.;
+#[cfg(feature = "render")]
 pub const PADDING: f32 = 5.;
 
+#[cfg(feature = "render")]
 pub trait UiExtension {
     fn color(&mut self, id: Id, label: &str, data: &mut Color);
     fn rule(&mut self);
 }
 
+#[cfg(feature = "render")]
 impl UiExtension for Ui {
     fn color(&mut self, id: Id, label: &str, data: &mut Color) {
         self.separator();
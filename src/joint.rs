@@ -1,28 +1,68 @@
 use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "render")]
 use crate::{
-    body::{Body, BodyDescriptor, Side},
+    body::Side,
     constants::{DEBUG_COLOR, DEBUG_LINE_THICKNESS},
 };
+use crate::{
+    body::{Body, BodyDescriptor},
+    constraints::RegistryError,
+    random::Rng,
+    timestep::blend_factor,
+};
 
 #[derive(Clone, Debug)]
 pub struct Joint {
+    /// Lets constraints elsewhere in the tree of bodies refer to this joint.
+    pub name: Option<String>,
     pub pos: Vec2,
+    /// The position before the last step, from which the joint's velocity is worked out when it
+    /// has inertia.
+    pub previous_pos: Vec2,
     pub radius: f32,
     pub angle: f32,
+    /// Keeps the joint moving between steps, rather than it stopping as soon as nothing pulls it.
+    pub inertia: Option<Inertia>,
     pub bodies: Vec<Body>,
 }
 
-#[derive(Clone, Debug)]
+/// Verlet integration settings for a joint, giving it secondary motion such as a swinging tail.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Inertia {
+    /// The time, in seconds, taken for the joint to lose half of its speed.
+    pub damping_half_life: f32,
+    /// A constant acceleration, in units per second squared.
+    pub gravity: Vec2,
+}
+
+impl Default for Inertia {
+    fn default() -> Self {
+        Self {
+            damping_half_life: 0.2,
+            gravity: Vec2::ZERO,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct JointDescriptor {
+    /// Lets constraints elsewhere in the tree of bodies refer to this joint.
+    pub name: Option<String>,
     pub radius: f32,
+    pub inertia: Option<Inertia>,
     pub bodies: Vec<BodyDescriptor>,
 }
 
 impl Default for JointDescriptor {
     fn default() -> Self {
         Self {
+            name: None,
             radius: 5.,
+            inertia: None,
             bodies: Vec::new(),
         }
     }
@@ -42,15 +82,58 @@ impl From<JointDescriptor> for Joint {
 
 impl Joint {
     pub fn new(descriptor: JointDescriptor) -> Self {
-        let JointDescriptor { radius, bodies } = descriptor;
-        Self {
+        Self::new_with_rng(descriptor, &mut Rng::default())
+    }
+
+    /// Builds the joint, drawing any randomness for its inner bodies from `rng`.
+    ///
+    /// # Panics
+    ///
+    /// If any custom constraint of its inner bodies can't be built.
+    pub fn new_with_rng(descriptor: JointDescriptor, rng: &mut Rng) -> Self {
+        Self::try_new_with_rng(descriptor, rng).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Builds the joint like [`Joint::new_with_rng`], failing if any custom constraint of its
+    /// inner bodies can't be built.
+    pub fn try_new_with_rng(
+        descriptor: JointDescriptor,
+        rng: &mut Rng,
+    ) -> Result<Self, RegistryError> {
+        let JointDescriptor {
+            name,
+            radius,
+            inertia,
+            bodies,
+        } = descriptor;
+        Ok(Self {
+            name,
             radius,
             pos: Vec2::ZERO,
+            previous_pos: Vec2::ZERO,
             angle: 0.,
-            bodies: bodies.into_iter().map(Into::into).collect::<Vec<_>>(),
-        }
+            inertia,
+            bodies: bodies
+                .into_iter()
+                .map(|body| Body::try_new_with_rng(body, rng))
+                .collect::<Result<Vec<_>, _>>()?,
+        })
+    }
+
+    /// Carries the joint along with its velocity from the last step, if it has inertia. Any
+    /// corrections made by constraints since then become part of that velocity.
+    pub fn integrate(&mut self, dt: f32) {
+        let Some(inertia) = self.inertia else {
+            return;
+        };
+
+        let velocity =
+            (self.pos - self.previous_pos) * (1. - blend_factor(inertia.damping_half_life, dt));
+        self.previous_pos = self.pos;
+        self.pos += velocity + inertia.gravity * dt * dt;
     }
 
+    #[cfg(feature = "render")]
     pub fn draw(&self, side: Side) {
         for body in &self.bodies {
             if body.side == side {
@@ -59,6 +142,7 @@ impl Joint {
         }
     }
 
+    #[cfg(feature = "render")]
     pub fn debug_draw(&self) {
         draw_circle_lines(
             self.pos.x,
@@ -0,0 +1,28 @@
+//! Procedural animation of bodies made up of chains of joints.
+//!
+//! Bodies are built from a [`BodyDescriptor`] and stepped with [`Body::apply_constraints`], usually
+//! through a [`timestep::FixedTimestep`]. Drawing with macroquad is only available with the `render` feature
+//! (enabled by default), so the solver can be used without a window. Macroquad is still depended on
+//! without the feature, for its vector and colour types, but is never asked to draw or open a
+//! window.
+
+pub mod body;
+pub mod constants;
+pub mod constraints;
+pub mod definition;
+pub mod export;
+pub mod extensions;
+pub mod joint;
+pub mod obstacle;
+pub mod path;
+pub mod random;
+pub mod serialization;
+pub mod spatial;
+pub mod steering;
+pub mod stroke;
+pub mod timestep;
+pub mod world;
+
+pub use body::{AttachmentPoint, Body, BodyDescriptor, Side};
+pub use constraints::{Constraint, ConstraintDescriptor};
+pub use joint::{Joint, JointDescriptor};
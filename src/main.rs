@@ -1,47 +1,111 @@
-mod body;
-mod constants;
-mod constraints;
-mod extensions;
-mod joint;
-
-use crate::body::Side;
 use std::{
     collections::HashSet,
-    f32::consts::PI,
+    f32::consts::{PI, TAU},
+    fmt, fs, io,
     sync::atomic::{AtomicU64, Ordering},
 };
 
-use body::{Body, BodyDescriptor};
-use constants::BACKGROUND_COLOR;
-use constraints::{
-    AngleConstraintDescriptor, ConstraintDescriptor, DistanceConstraintDescriptor,
-    FabrikConstraintDescriptor,
-};
-use extensions::{ColorExtension, UiExtension, UI_WIDTH};
-use joint::JointDescriptor;
 use macroquad::{
     camera::set_camera,
     prelude::*,
     ui::{
         hash, root_ui,
         widgets::{self},
-        Skin,
+        Skin, Ui,
+    },
+};
+use rust_procedural_animation::{
+    body::MAX_OUTLINE_SUBDIVISIONS,
+    constants::{BACKGROUND_COLOR, FIXED_TIMESTEP},
+    constraints::{
+        AngleConstraintDescriptor, AngleCorrection, AngleRange, BendDirection,
+        CollisionConstraintDescriptor, ConstraintDescriptor, DistanceConstraintDescriptor,
+        FabrikConstraintDescriptor, GaitConstraintDescriptor, GaitPattern, JointLimit,
+        PinConstraintDescriptor, PinTarget, Spring, SpringConstraintDescriptor, MAX_OVERSHOOT,
     },
+    definition,
+    export::{raster, recording::Recorder, svg},
+    extensions::{ColorExtension, UiExtension, UI_WIDTH},
+    joint::Inertia,
+    obstacle::Obstacle,
+    path::{lemniscate, Playback, SplineKind, SplinePath},
+    random::Rng,
+    serialization,
+    steering::{Steering, SteeringBehaviour},
+    stroke::LineJoin,
+    timestep::FixedTimestep,
+    world::{Creature, HeadController, World},
+    Body, BodyDescriptor, JointDescriptor, Side,
 };
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq)]
+/// The path used by the preset Save and Load buttons, unless one is given on the command line.
+const DEFAULT_PRESET_PATH: &str = "preset.ron";
+/// The path used by the definition Load and Export buttons.
+const DEFAULT_DEFINITION_PATH: &str = "creature.ron";
+/// The path used by the SVG export button.
+const DEFAULT_SVG_PATH: &str = "creature.svg";
+/// The path used by the PNG export button.
+const DEFAULT_PNG_PATH: &str = "creature.png";
+/// The width and height of exported PNG snapshots, in pixels.
+const PNG_SIZE: u32 = 512;
+/// The path used by the record button. Recordings are saved as APNGs when this ends in `.png`.
+const DEFAULT_RECORDING_PATH: &str = "creature.gif";
+/// How many creatures the "Spawn flock" button adds.
+const FLOCK_SIZE: usize = 50;
+/// Beyond this many creatures, the UI steps through them rather than listing them all.
+const MAX_LISTED_CREATURES: usize = 10;
+/// How close, in world units, the mouse has to be to a spline control point to pick it up.
+const CONTROL_POINT_RADIUS: f32 = 12.;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 struct BodyConfiguration {
+    /// Seeds the jitter applied when building the body, as well as randomly added shapes and legs.
+    seed: u64,
     angle_constraint: f32,
+    /// How far along the body the stiff torso reaches, from 0 to 1. The rest is a flexible tail.
+    torso_ratio: f32,
+    /// The time, in seconds, taken for the torso to straighten half of the way.
+    torso_half_life: f32,
+    /// Whether bends are corrected by moving the joints on both sides of them.
+    distribute_bends: bool,
     radius: f32,
     shapes: Vec<BodyShape>,
+    /// Shapes the distance between neighbouring joints along the body, like `shapes` does for
+    /// the radius.
+    distance_shapes: Vec<BodyShape>,
     joints: f32,
     joint_distance: f32,
     legs: Vec<LegConfiguration>,
+    /// The order in which the legs take their steps.
+    gait: GaitPattern,
+    #[serde(with = "serialization::color")]
     color: Color,
+    /// Whether the body's joints are pushed apart when it curls up on itself.
+    self_collision: bool,
+    /// Whether the joints behind the head keep moving after it stops, so the tail swings.
+    inertia: bool,
+    /// The time, in seconds, taken for a swinging joint to lose half of its speed.
+    inertia_damping: f32,
+    /// Whether the joints are held together by springs, rather than kept a fixed distance apart.
+    /// Combined with inertia, this makes the body wobble.
+    soft_body: bool,
+    spring_stiffness: f32,
+    /// Whether the tail is tied to the centre of the world.
+    tether: bool,
+    /// How far the tail can stray from the centre of the world when tethered.
+    tether_length: f32,
+    /// How many pieces each edge of the body and leg outlines is split into to smooth them.
+    outline_subdivisions: f32,
+    /// How the outlines of the body and legs turn corners.
+    line_join: LineJoin,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 struct BodyShape {
+    #[serde(skip, default = "next_ui_id")]
     id: u64,
     amplitude: f32,
     constant_offset: f32,
@@ -50,21 +114,33 @@ struct BodyShape {
 
 pub static UI_ID: AtomicU64 = AtomicU64::new(1);
 
+fn next_ui_id() -> u64 {
+    UI_ID.fetch_add(1, Ordering::Relaxed)
+}
+
 impl BodyShape {
-    fn random() -> BodyShape {
+    fn random(rng: &mut Rng) -> BodyShape {
         Self {
-            amplitude: rand::RandomRange::gen_range(0., 30.),
-            constant_offset: rand::RandomRange::gen_range(0., 2. * PI),
-            frequency_multiplier: rand::RandomRange::gen_range(-12., 12.),
+            amplitude: rng.gen_range(0., 30.),
+            constant_offset: rng.gen_range(0., 2. * PI),
+            frequency_multiplier: rng.gen_range(-12., 12.),
             ..Default::default()
         }
     }
+
+    /// The sum of the shapes, `t` of the way along the body.
+    fn offset(shapes: &[BodyShape], t: f32) -> f32 {
+        shapes
+            .iter()
+            .map(|s| s.amplitude * (s.constant_offset + s.frequency_multiplier * t).sin())
+            .sum()
+    }
 }
 
 impl Default for BodyShape {
     fn default() -> Self {
         Self {
-            id: UI_ID.fetch_add(1, Ordering::Relaxed),
+            id: next_ui_id(),
             amplitude: 5.,
             constant_offset: 0.,
             frequency_multiplier: 1.,
@@ -75,17 +151,53 @@ impl Default for BodyShape {
 impl BodyConfiguration {
     fn sanitize(&mut self) {
         self.joints = self.joints.ceil();
+        self.outline_subdivisions = self.outline_subdivisions.ceil();
 
         for leg in &mut self.legs {
             leg.joints = leg.joints.ceil();
+            // Presets saved before the overshoot was capped may go up to 1.
+            leg.overshoot = leg.overshoot.min(MAX_OVERSHOOT);
+        }
+    }
+
+    /// Checks that a body can be built from the configuration, which the UI guarantees but a
+    /// preset loaded from disk may not.
+    fn validate(&self) -> Result<(), PresetError> {
+        let invalid = |reason: &str| Err(PresetError::Invalid(reason.to_string()));
+
+        if !(self.joints >= 1. && self.joints.is_finite()) {
+            return invalid("the body needs at least one joint");
+        }
+        if self
+            .legs
+            .iter()
+            .any(|leg| !(leg.joints >= 1. && leg.joints.is_finite()))
+        {
+            return invalid("every leg needs at least one joint");
         }
+        if !(self.radius.is_finite() && self.joint_distance.is_finite()) {
+            return invalid("the radius and joint distance must be finite");
+        }
+        if !(1. ..=MAX_OUTLINE_SUBDIVISIONS as f32).contains(&self.outline_subdivisions) {
+            return invalid(&format!(
+                "the outline smoothing must be between 1 and {MAX_OUTLINE_SUBDIVISIONS}"
+            ));
+        }
+
+        // Everything else is checked in the same way as a definition.
+        definition::validate(&body_descriptor(self))
+            .map_err(|err| PresetError::Invalid(err.to_string()))
     }
 }
 
 impl Default for BodyConfiguration {
     fn default() -> Self {
         Self {
+            seed: 0,
             angle_constraint: 0.9 * PI,
+            torso_ratio: 0.,
+            torso_half_life: 0.1,
+            distribute_bends: false,
             color: Color::from_hex(0x61A5B8),
             radius: 30.,
             joints: 20.,
@@ -110,6 +222,7 @@ impl Default for BodyConfiguration {
                     ..Default::default()
                 },
             ],
+            distance_shapes: Vec::new(),
             legs: vec![
                 LegConfiguration {
                     joint_distance: 30.,
@@ -122,12 +235,24 @@ impl Default for BodyConfiguration {
                     ..Default::default()
                 },
             ],
+            gait: GaitPattern::default(),
+            self_collision: true,
+            inertia: false,
+            inertia_damping: 0.2,
+            soft_body: false,
+            spring_stiffness: 400.,
+            tether: false,
+            tether_length: 200.,
+            outline_subdivisions: 4.,
+            line_join: LineJoin::Round,
         }
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 struct LegConfiguration {
+    #[serde(skip, default = "next_ui_id")]
     id: u64,
     angle: f32,
     joints: f32,
@@ -136,18 +261,25 @@ struct LegConfiguration {
     target_ratio: f32,
     target_max_distance: f32,
     thickness: f32,
+    step_duration: f32,
+    step_height: f32,
+    overshoot: f32,
+    /// Which way the knees of the right leg bend. The left leg bends the opposite way.
+    bend: BendDirection,
+    min_bend: f32,
+    max_bend: f32,
 }
 
 impl LegConfiguration {
-    fn random() -> Self {
+    fn random(rng: &mut Rng) -> Self {
         Self {
-            thickness: rand::RandomRange::gen_range(5., 25.),
-            angle: PI * rand::RandomRange::gen_range(0.5, 0.8),
+            thickness: rng.gen_range(5., 25.),
+            angle: PI * rng.gen_range(0.5, 0.8),
             joints: 3.,
-            joint_distance: rand::RandomRange::gen_range(20., 30.),
-            body_ratio: rand::RandomRange::gen_range(0., 1.),
-            target_ratio: rand::RandomRange::gen_range(0.4, 0.8),
-            target_max_distance: rand::RandomRange::gen_range(60., 80.),
+            joint_distance: rng.gen_range(20., 30.),
+            body_ratio: rng.gen_range(0., 1.),
+            target_ratio: rng.gen_range(0.4, 0.8),
+            target_max_distance: rng.gen_range(60., 80.),
             ..Default::default()
         }
     }
@@ -156,7 +288,7 @@ impl LegConfiguration {
 impl Default for LegConfiguration {
     fn default() -> Self {
         Self {
-            id: UI_ID.fetch_add(1, Ordering::Relaxed),
+            id: next_ui_id(),
             thickness: 12.,
             angle: PI * 0.75,
             joints: 3.,
@@ -164,16 +296,79 @@ impl Default for LegConfiguration {
             body_ratio: 0.4,
             target_ratio: 0.65,
             target_max_distance: 100.,
+            step_duration: 0.15,
+            step_height: 10.,
+            overshoot: 0.2,
+            bend: BendDirection::Positive,
+            min_bend: 0.,
+            max_bend: PI,
         }
     }
 }
 
+/// A creature preset, as saved to and loaded from disk.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Preset {
+    body: BodyConfiguration,
+    path: SplinePath,
+}
+
+#[derive(Debug)]
+enum PresetError {
+    Io(io::Error),
+    Parse(ron::error::SpannedError),
+    Serialize(ron::Error),
+    /// The preset parsed, but describes a creature which can't be built.
+    Invalid(String),
+}
+
+impl fmt::Display for PresetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PresetError::Io(err) => write!(f, "could not access preset: {err}"),
+            PresetError::Parse(err) => write!(f, "could not parse preset: {err}"),
+            PresetError::Serialize(err) => write!(f, "could not serialize preset: {err}"),
+            PresetError::Invalid(reason) => write!(f, "invalid preset: {reason}"),
+        }
+    }
+}
+
+impl Preset {
+    fn load(path: &str) -> Result<Self, PresetError> {
+        let contents = fs::read_to_string(path).map_err(PresetError::Io)?;
+        let mut preset: Preset = ron::from_str(&contents).map_err(PresetError::Parse)?;
+        preset.body.sanitize();
+        preset.body.validate()?;
+        Ok(preset)
+    }
+
+    fn save(&self, path: &str) -> Result<(), PresetError> {
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(PresetError::Serialize)?;
+        fs::write(path, contents).map_err(PresetError::Io)
+    }
+}
+
 #[macroquad::main("Procedural Animation")]
 async fn main() {
     configure_ui_skin();
 
-    let mut body_config = BodyConfiguration::default();
-    let mut last_body_config = body_config.clone();
+    let mut preset_path = DEFAULT_PRESET_PATH.to_string();
+    let mut definition_path = DEFAULT_DEFINITION_PATH.to_string();
+    let mut svg_path = DEFAULT_SVG_PATH.to_string();
+    let mut png_path = DEFAULT_PNG_PATH.to_string();
+    let mut recording_path = DEFAULT_RECORDING_PATH.to_string();
+    let mut status = None;
+    let mut preset = Preset::default();
+    if let Some(path) = std::env::args().nth(1) {
+        match Preset::load(&path) {
+            Ok(loaded) => preset = loaded,
+            Err(err) => eprintln!("{err}"),
+        }
+        preset_path = path;
+    }
+    let body_config = preset.body;
 
     let camera = Camera2D::from_display_rect(Rect::new(
         -screen_width() / 2.0,
@@ -184,46 +379,51 @@ async fn main() {
 
     set_camera(&camera);
 
-    let mut use_mouse = false;
     let mut debug = false;
+    let mut editing_path = false;
+    // The spline control point being dragged while editing the path.
+    let mut dragged_point = None;
+
+    let mut timestep = FixedTimestep::new(FIXED_TIMESTEP);
+
+    // Seeds new creatures, so that each one is different.
+    let mut spawn_rng = Rng::new(body_config.seed);
+    let mut world = World::new(Vec2::ONE);
+    let mut creature = spawn_creature(body_config, 0.);
+    creature.data.path = preset.path;
+    world.spawn(creature);
+    let mut selected = Some(0);
 
-    let mut body = build_body(&body_config);
     loop {
         // update
-        body.apply_constraints(None);
+        let (x, y) = mouse_position();
+        world.pointer = camera.screen_to_world(Vec2::new(x, y));
 
-        if is_mouse_button_pressed(MouseButton::Left)
-            && !root_ui().is_mouse_over(mouse_position().into())
-        {
-            use_mouse = !use_mouse;
+        let over_ui = root_ui().is_mouse_over(mouse_position().into());
+        if let Some(creature) = selected.and_then(|index| world.creatures.get_mut(index)) {
+            if editing_path {
+                edit_path_with_mouse(creature, world.pointer, over_ui, &mut dragged_point);
+            } else if is_mouse_button_pressed(MouseButton::Left) && !over_ui {
+                toggle_controller(creature);
+            }
         }
+        world.extent = camera.screen_to_world(Vec2::new(screen_width(), screen_height()));
 
-        let (x, y) = mouse_position();
-        let mouse_world = camera.screen_to_world(Vec2::new(x, y));
-        if let Some(first) = body.joints.first_mut() {
-            if use_mouse {
-                first.pos = first.pos.lerp(mouse_world, 0.1);
-            } else {
-                let t = get_time() as f32;
-                let a = 1.5;
-                let denominator = 1. + t.sin() * t.sin();
-                let x = a * t.cos() / denominator;
-                let y = 2. * a * t.sin() * t.cos() / denominator;
-                first.pos = first.pos.lerp(
-                    (Vec2::new(x, y)
-                        * (camera.screen_to_world(Vec2::new(screen_width(), screen_height()))))
-                        / 2.,
-                    0.1,
-                );
-            };
+        for _ in 0..timestep.advance(get_frame_time()) {
+            world.step(timestep.step);
         }
 
         // draw
         clear_background(BACKGROUND_COLOR);
-        body.draw();
+        world.draw();
 
         if debug {
-            body.debug_draw();
+            world.debug_draw();
+        }
+        if editing_path {
+            if let Some(creature) = selected.and_then(|index| world.creatures.get(index)) {
+                creature.data.path.debug_draw();
+            }
         }
 
         widgets::Window::new(
@@ -237,176 +437,828 @@ async fn main() {
             if ui.button(None, format!("Debug: {}", debug)) {
                 debug = !debug;
             }
-            ui.label(None, "Body");
-            ui.color(hash!(), "Color", &mut body_config.color);
-            ui.slider(hash!(), "Radius", 1.0..50.0, &mut body_config.radius);
-            ui.slider(
-                hash!(),
-                "Max Angle",
-                (PI / 2.)..PI,
-                &mut body_config.angle_constraint,
-            );
-            ui.slider(hash!(), "Joints", 1.0..50.0, &mut body_config.joints);
-            ui.slider(
-                hash!(),
-                "Joint distance",
-                1.0..50.0,
-                &mut body_config.joint_distance,
-            );
+            if ui.button(None, format!("Collisions: {}", world.collisions)) {
+                world.collisions = !world.collisions;
+            }
+            ui.label(None, "Obstacles (drawn when debugging)");
+            if ui.button(None, "Add circle") {
+                let center = random_point(&mut spawn_rng, world.extent);
+                let radius = spawn_rng.gen_range(20., 60.);
+                world.obstacles.push(Obstacle::Circle { center, radius });
+            }
+            if ui.button(None, "Add box") {
+                let center = random_point(&mut spawn_rng, world.extent);
+                let half_size =
+                    Vec2::new(spawn_rng.gen_range(20., 80.), spawn_rng.gen_range(20., 80.));
+                world.obstacles.push(Obstacle::Rect {
+                    min: center - half_size,
+                    max: center + half_size,
+                });
+            }
+            if ui.button(None, "Add triangle") {
+                let center = random_point(&mut spawn_rng, world.extent);
+                let rotation = spawn_rng.gen_range(0., 2. * PI);
+                let points = (0..3)
+                    .map(|i| {
+                        let angle = rotation + i as f32 * 2. * PI / 3.;
+                        center + spawn_rng.gen_range(40., 80.) * Vec2::from_angle(angle)
+                    })
+                    .collect();
+                world.obstacles.push(Obstacle::Polygon { points });
+            }
+            if ui.button(None, "Clear obstacles") {
+                world.obstacles.clear();
+            }
             ui.rule();
-            ui.tree_node(hash!(), "Shaping", |ui| {
-                if ui.button(None, "Add") {
-                    body_config.shapes.push(BodyShape::random());
+            ui.label(None, "Creatures");
+            if ui.button(None, "Spawn") {
+                let config = BodyConfiguration {
+                    seed: spawn_rng.next_u64(),
+                    ..Default::default()
+                };
+                let phase = spawn_rng.gen_range(0., 2. * PI);
+                selected = Some(world.spawn(spawn_creature(config, phase)));
+            }
+            if let Some(index) = selected {
+                if ui.button(None, "Delete") {
+                    world.remove(index);
+                    selected = world
+                        .creatures
+                        .len()
+                        .checked_sub(1)
+                        .map(|last| index.min(last));
                 }
-                let mut marked_for_deletion = HashSet::new();
-                for shape in &mut body_config.shapes {
-                    ui.slider(
-                        hash!("shape.amplitude", shape.id),
-                        "Amplitude",
-                        0.0..30.,
-                        &mut shape.amplitude,
-                    );
-                    ui.slider(
-                        hash!("shape.constant_offset", shape.id),
-                        "Offset",
-                        0.0..(2. * PI),
-                        &mut shape.constant_offset,
-                    );
-                    ui.slider(
-                        hash!("shape.frequency_multiplier", shape.id),
-                        "Frequency multiplier",
-                        -30.0..30.,
-                        &mut shape.frequency_multiplier,
-                    );
-                    if ui.button(None, "Delete") {
-                        marked_for_deletion.insert(shape.id);
+            }
+            if ui.button(None, "Spawn flock") {
+                for _ in 0..FLOCK_SIZE {
+                    let center = random_point(&mut spawn_rng, world.extent);
+                    selected = Some(world.spawn(spawn_boid(&mut spawn_rng, center)));
+                }
+            }
+            if ui.button(None, "Clear creatures") {
+                world.creatures.clear();
+                selected = None;
+            }
+            if world.creatures.len() <= MAX_LISTED_CREATURES {
+                for index in 0..world.creatures.len() {
+                    let marker = if selected == Some(index) { ">" } else { " " };
+                    if ui.button(None, format!("{marker} Creature {}", index + 1)) {
+                        selected = Some(index);
                     }
                 }
-                if !marked_for_deletion.is_empty() {
-                    body_config
-                        .shapes
-                        .retain(|shape| !marked_for_deletion.contains(&shape.id));
+            } else {
+                // Too many creatures to list, so they're stepped through instead.
+                let count = world.creatures.len();
+                let index = selected.unwrap_or(0);
+                ui.label(None, &format!("Creature {} of {count}", index + 1));
+                if ui.button(None, "Previous") {
+                    selected = Some((index + count - 1) % count);
                 }
-            });
-            ui.rule();
-            ui.tree_node(hash!(), "Legs", |ui| {
-                if ui.button(None, "Add") {
-                    body_config.legs.push(LegConfiguration::random());
+                ui.same_line(0.);
+                if ui.button(None, "Next") {
+                    selected = Some((index + 1) % count);
                 }
-                ui.separator();
-                let mut marked_for_deletion = HashSet::new();
-                let mut clones = Vec::new();
-                for leg in &mut body_config.legs {
-                    ui.slider(
-                        hash!("leg.thickness", leg.id),
-                        "Thickness",
-                        1.0..30.,
-                        &mut leg.thickness,
-                    );
-                    ui.slider(
-                        hash!("leg.angle", leg.id),
-                        "Angle",
-                        0.0..(2. * PI),
-                        &mut leg.angle,
-                    );
-                    ui.slider(
-                        hash!("leg.joints", leg.id),
-                        "Joints",
-                        2.0..10.0,
-                        &mut leg.joints,
-                    );
-                    ui.slider(
-                        hash!("leg.joint_distance", leg.id),
-                        "Joint distance",
-                        1.0..50.0,
-                        &mut leg.joint_distance,
-                    );
-                    ui.slider(
-                        hash!("leg.target_ratio", leg.id),
-                        "Target ratio",
-                        0.0..1.0,
-                        &mut leg.target_ratio,
-                    );
-                    ui.slider(
-                        hash!("leg.target_max_distance", leg.id),
-                        "Max target distance",
-                        1.0..200.0,
-                        &mut leg.target_max_distance,
-                    );
-                    ui.slider(
-                        hash!("leg.body_ratio", leg.id),
-                        "Body ratio",
-                        0.0..1.0,
-                        &mut leg.body_ratio,
-                    );
-                    if ui.button(None, "Duplicate") {
-                        let mut leg_duplicate = leg.clone();
-                        leg_duplicate.id = UI_ID.fetch_add(1, Ordering::Relaxed);
-                        clones.push(leg_duplicate);
+            }
+            ui.rule();
+            ui.label(None, "Flocking");
+            let flocking = &mut world.flocking;
+            ui.slider(hash!(), "Radius", 10.0..300.0, &mut flocking.radius);
+            ui.slider(
+                hash!(),
+                "Personal space",
+                0.0..150.0,
+                &mut flocking.separation_radius,
+            );
+            ui.slider(hash!(), "Separation", 0.0..5.0, &mut flocking.separation);
+            ui.slider(hash!(), "Alignment", 0.0..5.0, &mut flocking.alignment);
+            ui.slider(hash!(), "Cohesion", 0.0..5.0, &mut flocking.cohesion);
+            ui.rule();
+
+            let extent = world.extent;
+            let Some(creature) = selected.and_then(|index| world.creatures.get_mut(index)) else {
+                return;
+            };
+
+            if ui.button(
+                None,
+                format!("Head: {}", controller_name(&creature.controller)),
+            ) {
+                next_controller(creature, extent);
+            }
+            if let HeadController::Steering(steering) = &mut creature.controller {
+                ui.slider(hash!(), "Max speed", 10.0..1000.0, &mut steering.max_speed);
+                ui.slider(hash!(), "Max force", 10.0..3000.0, &mut steering.max_force);
+            }
+            ui.rule();
+            ui.label(None, "Spline path");
+            if ui.button(None, format!("Edit path: {editing_path}")) {
+                editing_path = !editing_path;
+                dragged_point = None;
+            }
+            if editing_path {
+                ui.label(None, "Click to add a point, drag to move it");
+                ui.label(None, "Right click to delete it");
+            }
+            let kind = match creature.data.path.kind {
+                SplineKind::CatmullRom => "Catmull-Rom",
+                SplineKind::Bezier => "Bezier",
+            };
+            if ui.button(None, format!("Curve: {kind}")) {
+                edit_path(creature, |path| {
+                    path.kind = match path.kind {
+                        SplineKind::CatmullRom => SplineKind::Bezier,
+                        SplineKind::Bezier => SplineKind::CatmullRom,
                     }
-                    if ui.button(None, "Delete") {
-                        marked_for_deletion.insert(leg.id);
+                });
+            }
+            let playback = match creature.data.path.playback {
+                Playback::Loop => "Loop",
+                Playback::PingPong => "Ping-pong",
+            };
+            if ui.button(None, format!("Playback: {playback}")) {
+                edit_path(creature, |path| {
+                    path.playback = match path.playback {
+                        Playback::Loop => Playback::PingPong,
+                        Playback::PingPong => Playback::Loop,
                     }
-                }
-                ui.separator();
-                body_config.legs.extend(clones);
-                if !marked_for_deletion.is_empty() {
-                    body_config
-                        .legs
-                        .retain(|leg| !marked_for_deletion.contains(&leg.id));
+                });
+            }
+            let mut speed = creature.data.path.speed;
+            ui.slider(hash!(), "Path speed", 10.0..1000.0, &mut speed);
+            if speed != creature.data.path.speed {
+                edit_path(creature, |path| path.speed = speed);
+            }
+            if ui.button(None, "Clear path") {
+                edit_path(creature, |path| path.points.clear());
+            }
+            ui.rule();
+            ui.input_text(hash!(), "Preset", &mut preset_path);
+            if ui.button(None, "Save") {
+                let preset = Preset {
+                    body: creature.data.config.clone(),
+                    path: creature.data.path.clone(),
+                };
+                status = Some(match preset.save(&preset_path) {
+                    Ok(()) => format!("Saved {preset_path}"),
+                    Err(err) => err.to_string(),
+                });
+            }
+            if ui.button(None, "Load") {
+                status = Some(match Preset::load(&preset_path) {
+                    Ok(preset) => {
+                        // The configuration is compared against itself further down, so the
+                        // body has to be rebuilt here.
+                        creature.data.seed_input = preset.body.seed.to_string();
+                        creature.data.rng = Rng::new(preset.body.seed);
+                        creature.body = build_body(&preset.body);
+                        creature.data.config = preset.body;
+                        edit_path(creature, |path| *path = preset.path);
+                        format!("Loaded {preset_path}")
+                    }
+                    Err(err) => err.to_string(),
+                });
+            }
+            ui.input_text(hash!(), "Definition", &mut definition_path);
+            if ui.button(None, "Load definition") {
+                status = Some(match definition::load(&definition_path) {
+                    Ok(loaded) => {
+                        creature.body = loaded;
+                        format!("Loaded {definition_path}")
+                    }
+                    Err(err) => err.to_string(),
+                });
+            }
+            if ui.button(None, "Export definition") {
+                status = Some(
+                    match definition::to_string(&body_descriptor(&creature.data.config)).and_then(
+                        |contents| {
+                            fs::write(&definition_path, contents)
+                                .map_err(definition::DefinitionError::Io)
+                        },
+                    ) {
+                        Ok(()) => format!("Exported {definition_path}"),
+                        Err(err) => err.to_string(),
+                    },
+                );
+            }
+            ui.input_text(hash!(), "SVG", &mut svg_path);
+            if ui.button(None, "Export SVG") {
+                status = Some(match svg::save(&creature.body, &svg_path) {
+                    Ok(()) => format!("Exported {svg_path}"),
+                    Err(err) => format!("could not export SVG: {err}"),
+                });
+            }
+            ui.input_text(hash!(), "PNG", &mut png_path);
+            if ui.button(None, "Export PNG") {
+                let canvas = raster::snapshot(&creature.body, PNG_SIZE, PNG_SIZE, BACKGROUND_COLOR);
+                status = Some(match canvas.save_png(&png_path) {
+                    Ok(()) => format!("Exported {png_path}"),
+                    Err(err) => format!("could not export PNG: {err}"),
+                });
+            }
+            ui.input_text(hash!(), "Recording", &mut recording_path);
+            if ui.button(None, "Record") {
+                let start = world.time + creature.data.phase;
+                let extent = world.extent;
+                let clip = Recorder::default().record_path(&mut creature.body.clone(), |t| {
+                    lemniscate(start + t) * extent / 2.
+                });
+                status = Some(match clip.save(&recording_path) {
+                    Ok(()) => format!("Recorded {recording_path}"),
+                    Err(err) => err.to_string(),
+                });
+            }
+            if let Some(status) = &status {
+                ui.label(None, status);
+            }
+            ui.rule();
+
+            let settings = &mut creature.data;
+            let last_body_config = settings.config.clone();
+            body_ui(
+                ui,
+                &mut settings.config,
+                &mut settings.rng,
+                &mut settings.seed_input,
+            );
+
+            // Sanitize UI state
+            settings.config.sanitize();
+
+            if settings.config.seed != last_body_config.seed {
+                settings.rng = Rng::new(settings.config.seed);
+            }
+
+            if settings.config != last_body_config {
+                creature.body = build_body(&settings.config);
+            }
+        });
+
+        next_frame().await;
+    }
+}
+
+/// Everything the UI keeps for each creature in the world.
+#[derive(Clone, Debug)]
+struct CreatureSettings {
+    config: BodyConfiguration,
+    /// Draws randomly added shapes and legs, seeded from the configuration.
+    rng: Rng,
+    seed_input: String,
+    /// How far along the lemniscate the creature is, in seconds.
+    phase: f32,
+    /// The path followed with [`HeadController::Spline`], which is copied into the controller
+    /// whenever it's edited.
+    path: SplinePath,
+}
+
+fn spawn_creature(config: BodyConfiguration, phase: f32) -> Creature<CreatureSettings> {
+    let body = build_body(&config);
+    let settings = CreatureSettings {
+        rng: Rng::new(config.seed),
+        seed_input: config.seed.to_string(),
+        config,
+        phase,
+        path: SplinePath::default(),
+    };
+    Creature::new(body, HeadController::Lemniscate { phase }, settings)
+}
+
+/// A small creature which flocks with the others, with its head at `center`.
+fn spawn_boid(rng: &mut Rng, center: Vec2) -> Creature<CreatureSettings> {
+    let config = BodyConfiguration {
+        seed: rng.next_u64(),
+        radius: 8.,
+        joints: 6.,
+        joint_distance: 8.,
+        shapes: Vec::new(),
+        legs: Vec::new(),
+        self_collision: false,
+        color: Color::from_hex(0xE0A458),
+        ..Default::default()
+    };
+    let phase = rng.gen_range(0., 2. * PI);
+    let mut creature = spawn_creature(config, phase);
+    creature.body.visit_joints_mut(&mut |joint| {
+        joint.pos += center;
+        joint.previous_pos += center;
+    });
+
+    let mut steering =
+        Steering::new(SteeringBehaviour::Flock).with_seed(creature.data.rng.next_u64());
+    steering.max_speed = 150.;
+    steering.velocity = Vec2::from_angle(phase) * steering.max_speed;
+    creature.controller = HeadController::Steering(steering);
+    creature
+}
+
+/// A random point within the world's extent.
+fn random_point(rng: &mut Rng, extent: Vec2) -> Vec2 {
+    Vec2::new(
+        rng.gen_range(-extent.x, extent.x),
+        rng.gen_range(-extent.y, extent.y),
+    )
+}
+
+/// Changes the creature's spline path, and the path its head follows if it's following one.
+fn edit_path(creature: &mut Creature<CreatureSettings>, edit: impl FnOnce(&mut SplinePath)) {
+    edit(&mut creature.data.path);
+    if let HeadController::Spline(path) = &mut creature.controller {
+        path.clone_from(&creature.data.path);
+    }
+}
+
+/// Adds a control point to the creature's spline path when clicking, moves the point under the
+/// mouse when dragging and deletes it when right clicking.
+fn edit_path_with_mouse(
+    creature: &mut Creature<CreatureSettings>,
+    pointer: Vec2,
+    over_ui: bool,
+    dragged: &mut Option<usize>,
+) {
+    if let Some(index) = *dragged {
+        if is_mouse_button_down(MouseButton::Left) {
+            edit_path(creature, |path| {
+                if let Some(point) = path.points.get_mut(index) {
+                    *point = pointer;
                 }
             });
-        });
+        } else {
+            *dragged = None;
+        }
+        return;
+    }
 
-        // Sanitize UI state
-        body_config.sanitize();
+    if over_ui {
+        return;
+    }
 
-        if body_config != last_body_config {
-            body = build_body(&body_config);
+    let hovered = creature
+        .data
+        .path
+        .control_point_at(pointer, CONTROL_POINT_RADIUS);
+    if is_mouse_button_pressed(MouseButton::Left) {
+        match hovered {
+            Some(index) => *dragged = Some(index),
+            None => edit_path(creature, |path| path.points.push(pointer)),
         }
+    } else if is_mouse_button_pressed(MouseButton::Right) {
+        if let Some(index) = hovered {
+            edit_path(creature, |path| {
+                path.points.remove(index);
+            });
+        }
+    }
+}
 
-        last_body_config = body_config.clone();
+/// Switches the head between following the mouse and following the lemniscate.
+fn toggle_controller(creature: &mut Creature<CreatureSettings>) {
+    creature.controller = match creature.controller {
+        HeadController::Pointer => HeadController::Lemniscate {
+            phase: creature.data.phase,
+        },
+        _ => HeadController::Pointer,
+    };
+}
 
-        next_frame().await;
+fn controller_name(controller: &HeadController) -> &'static str {
+    match controller {
+        HeadController::Lemniscate { .. } => "Path",
+        HeadController::Spline(_) => "Spline",
+        HeadController::Pointer => "Mouse",
+        HeadController::Idle => "Idle",
+        HeadController::Steering(steering) => match steering.behaviour {
+            SteeringBehaviour::Seek => "Seek mouse",
+            SteeringBehaviour::Arrive { .. } => "Arrive at mouse",
+            SteeringBehaviour::Flee { .. } => "Flee mouse",
+            SteeringBehaviour::Wander { .. } => "Wander",
+            SteeringBehaviour::Pursue => "Pursue",
+            SteeringBehaviour::Evade { .. } => "Evade",
+            SteeringBehaviour::FollowPath { .. } => "Follow waypoints",
+            SteeringBehaviour::Flock => "Flock",
+        },
+    }
+}
+
+/// Cycles the head through following the lemniscate, the creature's spline path, the mouse and
+/// each steering behaviour.
+fn next_controller(creature: &mut Creature<CreatureSettings>, extent: Vec2) {
+    let behaviour = match &creature.controller {
+        HeadController::Lemniscate { .. } => {
+            creature.controller = HeadController::Spline(creature.data.path.clone());
+            return;
+        }
+        HeadController::Spline(_) => None,
+        HeadController::Pointer => Some(SteeringBehaviour::Seek),
+        HeadController::Idle => None,
+        HeadController::Steering(steering) => match steering.behaviour {
+            SteeringBehaviour::Seek => Some(SteeringBehaviour::Arrive {
+                slowing_radius: 150.,
+            }),
+            SteeringBehaviour::Arrive { .. } => {
+                Some(SteeringBehaviour::Flee { panic_radius: 250. })
+            }
+            SteeringBehaviour::Flee { .. } => Some(SteeringBehaviour::Wander {
+                distance: 80.,
+                radius: 40.,
+                jitter: 12.,
+            }),
+            SteeringBehaviour::Wander { .. } => Some(SteeringBehaviour::Pursue),
+            SteeringBehaviour::Pursue => Some(SteeringBehaviour::Evade { panic_radius: 250. }),
+            SteeringBehaviour::Evade { .. } => Some(SteeringBehaviour::FollowPath {
+                // A few points around the lemniscate, so the path looks familiar.
+                points: (0..12)
+                    .map(|i| lemniscate(i as f32 * TAU / 12.) * extent / 2.)
+                    .collect(),
+                waypoint_radius: 30.,
+                looped: true,
+            }),
+            SteeringBehaviour::FollowPath { .. } => Some(SteeringBehaviour::Flock),
+            SteeringBehaviour::Flock => {
+                creature.controller = HeadController::Lemniscate {
+                    phase: creature.data.phase,
+                };
+                return;
+            }
+        },
+    };
+
+    creature.controller = match behaviour {
+        Some(behaviour) => HeadController::Steering(
+            Steering::new(behaviour).with_seed(creature.data.rng.next_u64()),
+        ),
+        None => HeadController::Pointer,
+    };
+}
+
+fn body_ui(
+    ui: &mut Ui,
+    body_config: &mut BodyConfiguration,
+    rng: &mut Rng,
+    seed_input: &mut String,
+) {
+    ui.label(None, "Body");
+    ui.input_text(hash!(), "Seed", seed_input);
+    if let Ok(seed) = seed_input.trim().parse() {
+        body_config.seed = seed;
+    }
+    if ui.button(None, "Random seed") {
+        body_config.seed = rng.next_u64();
+        *seed_input = body_config.seed.to_string();
+    }
+    ui.color(hash!(), "Color", &mut body_config.color);
+    ui.slider(
+        hash!(),
+        "Outline smoothing",
+        1.0..MAX_OUTLINE_SUBDIVISIONS as f32,
+        &mut body_config.outline_subdivisions,
+    );
+    let line_join = match body_config.line_join {
+        LineJoin::Miter => "Miter",
+        LineJoin::Round => "Round",
+    };
+    if ui.button(None, format!("Outline joins: {line_join}")) {
+        body_config.line_join = match body_config.line_join {
+            LineJoin::Miter => LineJoin::Round,
+            LineJoin::Round => LineJoin::Miter,
+        };
+    }
+    ui.slider(hash!(), "Radius", 1.0..50.0, &mut body_config.radius);
+    ui.slider(
+        hash!(),
+        "Max Angle",
+        (PI / 2.)..PI,
+        &mut body_config.angle_constraint,
+    );
+    ui.slider(
+        hash!(),
+        "Torso ratio",
+        0.0..1.0,
+        &mut body_config.torso_ratio,
+    );
+    ui.slider(
+        hash!(),
+        "Torso half-life",
+        0.01..1.0,
+        &mut body_config.torso_half_life,
+    );
+    if ui.button(
+        None,
+        format!("Distribute bends: {}", body_config.distribute_bends),
+    ) {
+        body_config.distribute_bends = !body_config.distribute_bends;
+    }
+    ui.slider(hash!(), "Joints", 1.0..50.0, &mut body_config.joints);
+    ui.slider(
+        hash!(),
+        "Joint distance",
+        1.0..50.0,
+        &mut body_config.joint_distance,
+    );
+    if ui.button(
+        None,
+        format!("Self collision: {}", body_config.self_collision),
+    ) {
+        body_config.self_collision = !body_config.self_collision;
+    }
+    if ui.button(None, format!("Inertia: {}", body_config.inertia)) {
+        body_config.inertia = !body_config.inertia;
+    }
+    ui.slider(
+        hash!(),
+        "Inertia damping",
+        0.01..1.0,
+        &mut body_config.inertia_damping,
+    );
+    if ui.button(None, format!("Soft body: {}", body_config.soft_body)) {
+        body_config.soft_body = !body_config.soft_body;
+    }
+    ui.slider(
+        hash!(),
+        "Spring stiffness",
+        10.0..2000.0,
+        &mut body_config.spring_stiffness,
+    );
+    if ui.button(None, format!("Tether: {}", body_config.tether)) {
+        body_config.tether = !body_config.tether;
+    }
+    ui.slider(
+        hash!(),
+        "Tether length",
+        0.0..500.0,
+        &mut body_config.tether_length,
+    );
+    ui.rule();
+    ui.tree_node(hash!(), "Shaping", |ui| {
+        shapes_ui(ui, &mut body_config.shapes, rng)
+    });
+    ui.rule();
+    ui.tree_node(hash!(), "Segment shaping", |ui| {
+        shapes_ui(ui, &mut body_config.distance_shapes, rng)
+    });
+    ui.rule();
+    ui.tree_node(hash!(), "Legs", |ui| {
+        if ui.button(None, "Add") {
+            body_config.legs.push(LegConfiguration::random(rng));
+        }
+        if ui.button(None, format!("Gait: {:?}", body_config.gait)) {
+            body_config.gait = next_gait(body_config.gait);
+        }
+        ui.separator();
+        let mut marked_for_deletion = HashSet::new();
+        let mut clones = Vec::new();
+        for leg in &mut body_config.legs {
+            ui.slider(
+                hash!("leg.thickness", leg.id),
+                "Thickness",
+                1.0..30.,
+                &mut leg.thickness,
+            );
+            ui.slider(
+                hash!("leg.angle", leg.id),
+                "Angle",
+                0.0..(2. * PI),
+                &mut leg.angle,
+            );
+            ui.slider(
+                hash!("leg.joints", leg.id),
+                "Joints",
+                2.0..10.0,
+                &mut leg.joints,
+            );
+            ui.slider(
+                hash!("leg.joint_distance", leg.id),
+                "Joint distance",
+                1.0..50.0,
+                &mut leg.joint_distance,
+            );
+            ui.slider(
+                hash!("leg.target_ratio", leg.id),
+                "Target ratio",
+                0.0..1.0,
+                &mut leg.target_ratio,
+            );
+            ui.slider(
+                hash!("leg.target_max_distance", leg.id),
+                "Max target distance",
+                1.0..200.0,
+                &mut leg.target_max_distance,
+            );
+            ui.slider(
+                hash!("leg.step_duration", leg.id),
+                "Step duration",
+                0.0..0.5,
+                &mut leg.step_duration,
+            );
+            ui.slider(
+                hash!("leg.step_height", leg.id),
+                "Step height",
+                0.0..40.0,
+                &mut leg.step_height,
+            );
+            ui.slider(
+                hash!("leg.overshoot", leg.id),
+                "Overshoot",
+                0.0..MAX_OVERSHOOT,
+                &mut leg.overshoot,
+            );
+            if ui.button(None, format!("Knee bend: {:?}", leg.bend)) {
+                leg.bend = next_bend(leg.bend);
+            }
+            ui.slider(
+                hash!("leg.min_bend", leg.id),
+                "Min bend",
+                0.0..PI,
+                &mut leg.min_bend,
+            );
+            ui.slider(
+                hash!("leg.max_bend", leg.id),
+                "Max bend",
+                0.0..PI,
+                &mut leg.max_bend,
+            );
+            ui.slider(
+                hash!("leg.body_ratio", leg.id),
+                "Body ratio",
+                0.0..1.0,
+                &mut leg.body_ratio,
+            );
+            if ui.button(None, "Duplicate") {
+                let mut leg_duplicate = leg.clone();
+                leg_duplicate.id = next_ui_id();
+                clones.push(leg_duplicate);
+            }
+            if ui.button(None, "Delete") {
+                marked_for_deletion.insert(leg.id);
+            }
+        }
+        ui.separator();
+        body_config.legs.extend(clones);
+        if !marked_for_deletion.is_empty() {
+            body_config
+                .legs
+                .retain(|leg| !marked_for_deletion.contains(&leg.id));
+        }
+    });
+}
+
+/// Controls for a list of sine waves, such as those shaping the radius along the body.
+fn shapes_ui(ui: &mut Ui, shapes: &mut Vec<BodyShape>, rng: &mut Rng) {
+    if ui.button(None, "Add") {
+        shapes.push(BodyShape::random(rng));
+    }
+    let mut marked_for_deletion = HashSet::new();
+    for shape in shapes.iter_mut() {
+        ui.slider(
+            hash!("shape.amplitude", shape.id),
+            "Amplitude",
+            0.0..30.,
+            &mut shape.amplitude,
+        );
+        ui.slider(
+            hash!("shape.constant_offset", shape.id),
+            "Offset",
+            0.0..(2. * PI),
+            &mut shape.constant_offset,
+        );
+        ui.slider(
+            hash!("shape.frequency_multiplier", shape.id),
+            "Frequency multiplier",
+            -30.0..30.,
+            &mut shape.frequency_multiplier,
+        );
+        if ui.button(None, "Delete") {
+            marked_for_deletion.insert(shape.id);
+        }
+    }
+    if !marked_for_deletion.is_empty() {
+        shapes.retain(|shape| !marked_for_deletion.contains(&shape.id));
+    }
+}
+
+fn next_gait(gait: GaitPattern) -> GaitPattern {
+    match gait {
+        GaitPattern::Free => GaitPattern::Alternating,
+        GaitPattern::Alternating => GaitPattern::Tripod,
+        GaitPattern::Tripod => GaitPattern::Wave,
+        GaitPattern::Wave => GaitPattern::Free,
+    }
+}
+
+fn next_bend(bend: BendDirection) -> BendDirection {
+    match bend {
+        BendDirection::Either => BendDirection::Positive,
+        BendDirection::Positive => BendDirection::Negative,
+        BendDirection::Negative => BendDirection::Either,
     }
 }
 
 fn build_body(body_config: &BodyConfiguration) -> Body {
+    Body::new_with_rng(
+        body_descriptor(body_config),
+        &mut Rng::new(body_config.seed),
+    )
+}
+
+fn body_descriptor(body_config: &BodyConfiguration) -> BodyDescriptor {
     let total_joints = body_config.joints as usize;
+    let distances = (0..total_joints.saturating_sub(1))
+        .map(|i| {
+            let distance = body_config.joint_distance
+                + BodyShape::offset(&body_config.distance_shapes, i as f32 / total_joints as f32);
+            distance.max(1.)
+        })
+        .collect::<Vec<_>>();
+    // The torso relaxes back to straight, while the tail behind it bends freely.
+    let torso_joints = (body_config.torso_ratio * total_joints as f32) as usize;
     let mut body = BodyDescriptor {
         fill_color: body_config.color,
+        line_join: body_config.line_join,
+        outline_subdivisions: body_config.outline_subdivisions as usize,
         joints: (0..total_joints)
             .map(|i| {
                 let radius = body_config.radius
-                    + body_config
-                        .shapes
-                        .iter()
-                        .map(|s| {
-                            s.amplitude
-                                * (s.constant_offset
-                                    + s.frequency_multiplier * (i as f32 / total_joints as f32))
-                                    .sin()
-                        })
-                        .sum::<f32>();
+                    + BodyShape::offset(&body_config.shapes, i as f32 / total_joints as f32);
                 JointDescriptor {
                     radius: radius.max(1.),
+                    // The head is led along its path, so only the rest of the body swings.
+                    inertia: (body_config.inertia && i > 0).then_some(Inertia {
+                        damping_half_life: body_config.inertia_damping,
+                        ..Default::default()
+                    }),
                     ..Default::default()
                 }
             })
             .collect::<Vec<_>>(),
         constraints: vec![
-            ConstraintDescriptor::Distance(DistanceConstraintDescriptor {
-                distance: body_config.joint_distance,
-                ..Default::default()
-            }),
+            if body_config.soft_body {
+                ConstraintDescriptor::Spring(SpringConstraintDescriptor {
+                    springs: distances
+                        .iter()
+                        .map(|&rest_length| Spring {
+                            rest_length,
+                            stiffness: body_config.spring_stiffness,
+                            ..Default::default()
+                        })
+                        .collect(),
+                })
+            } else {
+                ConstraintDescriptor::Distance(DistanceConstraintDescriptor {
+                    distance: body_config.joint_distance,
+                    distances: distances.clone(),
+                    ..Default::default()
+                })
+            },
             ConstraintDescriptor::Angle(AngleConstraintDescriptor {
                 angle: body_config.angle_constraint,
-                rate: 0.5,
+                ranges: (0..torso_joints)
+                    .map(|_| AngleRange {
+                        min: body_config.angle_constraint,
+                        rest: Some(PI),
+                        rest_half_life: body_config.torso_half_life,
+                        ..Default::default()
+                    })
+                    .collect(),
+                correction: if body_config.distribute_bends {
+                    AngleCorrection::Distributed
+                } else {
+                    AngleCorrection::Forward
+                },
+                half_life: 0.016,
+            }),
+            ConstraintDescriptor::Gait(GaitConstraintDescriptor {
+                pattern: body_config.gait,
+                ..Default::default()
             }),
         ],
         ..Default::default()
     };
 
+    if body_config.self_collision {
+        // Neighbouring joints always overlap, so only collide joints which are further apart
+        // along the spine than the widest joint.
+        let max_radius = body.joints.iter().map(|j| j.radius).fold(0., f32::max);
+        let min_distance = distances
+            .iter()
+            .copied()
+            .fold(body_config.joint_distance, f32::min);
+        body.add_constraint(ConstraintDescriptor::Collision(
+            CollisionConstraintDescriptor {
+                skip: (2. * max_radius / min_distance).ceil() as usize,
+                half_life: 0.016,
+            },
+        ));
+    }
+
+    if body_config.tether {
+        body.add_constraint(ConstraintDescriptor::Pin(PinConstraintDescriptor {
+            joint: body.joints.len().saturating_sub(1),
+            target: PinTarget::Position(Vec2::ZERO),
+            distance: body_config.tether_length,
+            half_life: 0.016,
+        }));
+    }
+
     // eyes
     let head = body.joints.first_mut().unwrap();
     for mult in [-1., 1.] {
@@ -434,6 +1286,7 @@ fn build_body(body_config: &BodyConfiguration) -> Body {
                     ..Default::default()
                 },
             ],
+            ..Default::default()
         };
 
         head.add_body(BodyDescriptor {
@@ -447,12 +1300,21 @@ fn build_body(body_config: &BodyConfiguration) -> Body {
         });
     }
 
-    for leg in &body_config.legs {
-        let joint_index = (body.joints.len() as f32 * leg.body_ratio) as usize;
+    // Pairs are numbered from front to back, which the order of the list needn't follow.
+    let mut legs = body_config
+        .legs
+        .iter()
+        .map(|leg| ((body.joints.len() as f32 * leg.body_ratio) as usize, leg))
+        .filter(|(joint_index, _)| *joint_index < body.joints.len())
+        .collect::<Vec<_>>();
+    legs.sort_by_key(|(joint_index, _)| *joint_index);
+    for (pair, (joint_index, leg)) in legs.into_iter().enumerate() {
         if let Some(joint) = body.joints.get_mut(joint_index) {
             for mult in [-1., 1.] {
                 joint.add_body(BodyDescriptor {
                     fill_color: body_config.color.mul(Color::from_hex(0xDDDDDD)),
+                    line_join: body_config.line_join,
+                    outline_subdivisions: body_config.outline_subdivisions as usize,
                     joints: (0..(leg.joints as usize))
                         .map(|_| JointDescriptor {
                             radius: leg.thickness,
@@ -467,6 +1329,19 @@ fn build_body(body_config: &BodyConfiguration) -> Body {
                         target_angle: mult * leg.angle,
                         target_distance: (leg.joint_distance * leg.joints) * leg.target_ratio,
                         max_distance: leg.target_max_distance,
+                        step_duration: leg.step_duration,
+                        step_height: leg.step_height,
+                        overshoot: leg.overshoot,
+                        joint_limits: vec![JointLimit {
+                            bend: if mult < 0. {
+                                leg.bend.flipped()
+                            } else {
+                                leg.bend
+                            },
+                            min_angle: leg.min_bend,
+                            max_angle: leg.max_bend.max(leg.min_bend),
+                        }],
+                        pair: Some(pair),
                         ..Default::default()
                     })],
                     ..Default::default()
@@ -475,7 +1350,7 @@ fn build_body(body_config: &BodyConfiguration) -> Body {
         }
     }
 
-    return Body::new(body);
+    body
 }
 
 fn configure_ui_skin() {
@@ -0,0 +1,147 @@
+use macroquad::math::Vec2;
+#[cfg(feature = "render")]
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "render")]
+use crate::constants::{DEBUG_COLOR, DEBUG_LINE_THICKNESS};
+use crate::joint::Joint;
+
+/// Static world geometry which joints are kept out of.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Obstacle {
+    Circle {
+        center: Vec2,
+        radius: f32,
+    },
+    /// An axis-aligned box.
+    Rect {
+        min: Vec2,
+        max: Vec2,
+    },
+    /// A simple polygon, wound in either direction.
+    Polygon {
+        points: Vec<Vec2>,
+    },
+}
+
+impl Obstacle {
+    /// The shortest offset which moves a circle out of the obstacle, or zero if they don't
+    /// overlap.
+    pub fn push_out(&self, center: Vec2, radius: f32) -> Vec2 {
+        match self {
+            Obstacle::Circle {
+                center: obstacle_center,
+                radius: obstacle_radius,
+            } => {
+                let delta = center - *obstacle_center;
+                let distance = delta.length();
+                let overlap = obstacle_radius + radius - distance;
+                if overlap <= 0. {
+                    return Vec2::ZERO;
+                }
+                delta.normalize_or(Vec2::X) * overlap
+            }
+            Obstacle::Rect { min, max } => {
+                let closest = center.clamp(*min, *max);
+                if closest != center {
+                    return push_from_surface(center, closest, radius, false);
+                }
+
+                // The centre is inside, so leave through the nearest face.
+                let faces = [
+                    (center.x - min.x, -Vec2::X),
+                    (max.x - center.x, Vec2::X),
+                    (center.y - min.y, -Vec2::Y),
+                    (max.y - center.y, Vec2::Y),
+                ];
+                let (depth, normal) = faces
+                    .into_iter()
+                    .min_by(|(a, _), (b, _)| a.total_cmp(b))
+                    .unwrap_or((0., Vec2::X));
+                normal * (depth + radius)
+            }
+            Obstacle::Polygon { points } => {
+                let Some(closest) = closest_point_on_edges(points, center) else {
+                    return Vec2::ZERO;
+                };
+                push_from_surface(center, closest, radius, contains(points, center))
+            }
+        }
+    }
+
+    #[cfg(feature = "render")]
+    pub fn debug_draw(&self) {
+        match self {
+            Obstacle::Circle { center, radius } => draw_circle_lines(
+                center.x,
+                center.y,
+                *radius,
+                DEBUG_LINE_THICKNESS,
+                DEBUG_COLOR,
+            ),
+            Obstacle::Rect { min, max } => draw_rectangle_lines(
+                min.x,
+                min.y,
+                max.x - min.x,
+                max.y - min.y,
+                DEBUG_LINE_THICKNESS,
+                DEBUG_COLOR,
+            ),
+            Obstacle::Polygon { points } => {
+                for (a, b) in points.iter().zip(points.iter().cycle().skip(1)) {
+                    draw_line(a.x, a.y, b.x, b.y, DEBUG_LINE_THICKNESS, DEBUG_COLOR);
+                }
+            }
+        }
+    }
+}
+
+/// Moves the joint `rate` of the way out of each of the obstacles it overlaps.
+pub fn avoid_obstacles(joint: &mut Joint, obstacles: &[Obstacle], rate: f32) {
+    for obstacle in obstacles {
+        joint.pos += obstacle.push_out(joint.pos, joint.radius) * rate;
+    }
+}
+
+fn push_from_surface(center: Vec2, closest: Vec2, radius: f32, inside: bool) -> Vec2 {
+    let delta = closest - center;
+    let distance = delta.length();
+    if inside {
+        return delta.normalize_or(Vec2::X) * (distance + radius);
+    }
+
+    if distance >= radius {
+        return Vec2::ZERO;
+    }
+    -delta.normalize_or(Vec2::X) * (radius - distance)
+}
+
+fn closest_point_on_edges(points: &[Vec2], point: Vec2) -> Option<Vec2> {
+    points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(a, b)| {
+            let edge = *b - *a;
+            let t =
+                ((point - *a).dot(edge) / edge.length_squared().max(f32::EPSILON)).clamp(0., 1.);
+            *a + edge * t
+        })
+        .min_by(|a, b| {
+            a.distance_squared(point)
+                .total_cmp(&b.distance_squared(point))
+        })
+}
+
+/// Even-odd test for whether the point is inside the polygon.
+fn contains(points: &[Vec2], point: Vec2) -> bool {
+    let mut inside = false;
+    for (a, b) in points.iter().zip(points.iter().cycle().skip(1)) {
+        if (a.y > point.y) != (b.y > point.y)
+            && point.x < a.x + (point.y - a.y) * (b.x - a.x) / (b.y - a.y)
+        {
+            inside = !inside;
+        }
+    }
+    inside
+}
@@ -0,0 +1,225 @@
+//! Paths for the head of a body to follow.
+
+use macroquad::math::Vec2;
+#[cfg(feature = "render")]
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "render")]
+use crate::constants::{DEBUG_COLOR, DEBUG_LINE_THICKNESS};
+use crate::{body::Body, timestep::blend_factor};
+
+/// How finely each segment of a [`SplinePath`] is sampled when measuring and drawing it.
+const SAMPLES_PER_SEGMENT: usize = 16;
+
+/// A figure-of-eight, at `time` seconds along. It spans roughly `[-1.5, 1.5]` horizontally and
+/// `[-1.1, 1.1]` vertically, so it should be scaled to fit.
+pub fn lemniscate(time: f32) -> Vec2 {
+    let a = 1.5;
+    let denominator = 1. + time.sin() * time.sin();
+    let x = a * time.cos() / denominator;
+    let y = 2. * a * time.sin() * time.cos() / denominator;
+    Vec2::new(x, y)
+}
+
+/// Moves the head (first joint) of the body towards `target`, covering half of the remaining
+/// distance every `half_life` seconds.
+pub fn follow(body: &mut Body, target: Vec2, half_life: f32, dt: f32) {
+    if let Some(first) = body.joints.first_mut() {
+        first.pos = first.pos.lerp(target, blend_factor(half_life, dt));
+    }
+}
+
+/// How the control points of a [`SplinePath`] shape the curve.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SplineKind {
+    /// Passes through every point, curving smoothly between them.
+    #[default]
+    CatmullRom,
+    /// Cubic Bezier segments, where every third point is passed through and the two in between
+    /// are handles which pull the curve towards them. Points which don't complete a segment are
+    /// ignored.
+    Bezier,
+}
+
+/// What happens when the end of a [`SplinePath`] is reached.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Playback {
+    /// The path is closed, and is followed round and round.
+    #[default]
+    Loop,
+    /// The path is open, and is followed back and forth.
+    PingPong,
+}
+
+/// A curve through control points, followed at a constant speed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SplinePath {
+    pub points: Vec<Vec2>,
+    pub kind: SplineKind,
+    pub playback: Playback,
+    /// How fast the path is followed, in units per second.
+    pub speed: f32,
+}
+
+impl Default for SplinePath {
+    fn default() -> Self {
+        Self {
+            points: vec![
+                Vec2::new(-200., 0.),
+                Vec2::new(0., -150.),
+                Vec2::new(200., 0.),
+                Vec2::new(0., 150.),
+            ],
+            kind: SplineKind::CatmullRom,
+            playback: Playback::Loop,
+            speed: 200.,
+        }
+    }
+}
+
+impl SplinePath {
+    /// Whether the end of the path joins back up with its start.
+    pub fn is_closed(&self) -> bool {
+        self.playback == Playback::Loop
+    }
+
+    /// The number of curved segments in the path.
+    pub fn segments(&self) -> usize {
+        let count = self.points.len();
+        match (self.kind, self.is_closed()) {
+            (SplineKind::CatmullRom, true) if count >= 3 => count,
+            (SplineKind::CatmullRom, _) => count.saturating_sub(1),
+            (SplineKind::Bezier, true) => count / 3,
+            (SplineKind::Bezier, false) => count.saturating_sub(1) / 3,
+        }
+    }
+
+    /// The point `t` of the way along the path, where each segment spans a length of one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the path has no points.
+    pub fn point(&self, t: f32) -> Vec2 {
+        let segments = self.segments();
+        if segments == 0 {
+            return self.points[0];
+        }
+
+        let t = t.clamp(0., segments as f32);
+        let segment = (t as usize).min(segments - 1);
+        let t = t - segment as f32;
+
+        match self.kind {
+            SplineKind::CatmullRom => {
+                let [p0, p1, p2, p3] =
+                    [-1, 0, 1, 2].map(|offset| self.catmull_rom_point(segment as isize + offset));
+                0.5 * (2. * p1
+                    + (p2 - p0) * t
+                    + (2. * p0 - 5. * p1 + 4. * p2 - p3) * t * t
+                    + (3. * p1 - p0 - 3. * p2 + p3) * t * t * t)
+            }
+            SplineKind::Bezier => {
+                // Wraps the last anchor of a closed path back to the first point.
+                let used = 3 * segments + usize::from(!self.is_closed());
+                let [p0, p1, p2, p3] = [0, 1, 2, 3].map(|k| self.points[(3 * segment + k) % used]);
+                let u = 1. - t;
+                u * u * u * p0 + 3. * u * u * t * p1 + 3. * u * t * t * p2 + t * t * t * p3
+            }
+        }
+    }
+
+    /// The control point at `index`, wrapping around closed paths and repeating the ends of open
+    /// ones.
+    fn catmull_rom_point(&self, index: isize) -> Vec2 {
+        let count = self.points.len() as isize;
+        let index = if self.is_closed() && count >= 3 {
+            index.rem_euclid(count)
+        } else {
+            index.clamp(0, count - 1)
+        };
+        self.points[index as usize]
+    }
+
+    /// Points along the whole path, close enough together to be joined with straight lines.
+    pub fn polyline(&self) -> Vec<Vec2> {
+        if self.points.is_empty() {
+            return Vec::new();
+        }
+
+        let samples = self.segments() * SAMPLES_PER_SEGMENT;
+        (0..=samples)
+            .map(|sample| self.point(sample as f32 / SAMPLES_PER_SEGMENT as f32))
+            .collect()
+    }
+
+    /// Where along the path the head should be after `time` seconds, if the path has any points.
+    pub fn position(&self, time: f32) -> Option<Vec2> {
+        let polyline = self.polyline();
+        let first = *polyline.first()?;
+
+        let length: f32 = polyline
+            .windows(2)
+            .map(|pair| pair[0].distance(pair[1]))
+            .sum();
+        if length <= f32::EPSILON {
+            return Some(first);
+        }
+
+        let travelled = time * self.speed;
+        let mut distance = match self.playback {
+            Playback::Loop => travelled.rem_euclid(length),
+            Playback::PingPong => length - (travelled.rem_euclid(2. * length) - length).abs(),
+        };
+
+        for pair in polyline.windows(2) {
+            let segment = pair[0].distance(pair[1]);
+            if distance <= segment {
+                return Some(pair[0].lerp(pair[1], distance / segment.max(f32::EPSILON)));
+            }
+            distance -= segment;
+        }
+        polyline.last().copied()
+    }
+
+    /// The index of the control point nearest to `position`, if any is within `radius` of it.
+    pub fn control_point_at(&self, position: Vec2, radius: f32) -> Option<usize> {
+        self.points
+            .iter()
+            .enumerate()
+            .map(|(index, point)| (index, point.distance(position)))
+            .filter(|(_, distance)| *distance <= radius)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(index, _)| index)
+    }
+
+    /// Draws the curve along with its control points, and the handles of Bezier segments.
+    #[cfg(feature = "render")]
+    pub fn debug_draw(&self) {
+        for pair in self.polyline().windows(2) {
+            let [a, b] = [pair[0], pair[1]];
+            draw_line(a.x, a.y, b.x, b.y, DEBUG_LINE_THICKNESS, DEBUG_COLOR);
+        }
+
+        if self.kind == SplineKind::Bezier {
+            for pair in self.points.windows(2).step_by(3) {
+                let [a, b] = [pair[0], pair[1]];
+                draw_line(a.x, a.y, b.x, b.y, DEBUG_LINE_THICKNESS / 2., GRAY);
+            }
+            for pair in self.points.windows(2).skip(2).step_by(3) {
+                let [a, b] = [pair[0], pair[1]];
+                draw_line(a.x, a.y, b.x, b.y, DEBUG_LINE_THICKNESS / 2., GRAY);
+            }
+        }
+
+        for (index, point) in self.points.iter().enumerate() {
+            let handle = self.kind == SplineKind::Bezier && index % 3 != 0;
+            if handle {
+                draw_circle_lines(point.x, point.y, 5., DEBUG_LINE_THICKNESS, GRAY);
+            } else {
+                draw_circle(point.x, point.y, 6., DEBUG_COLOR);
+            }
+        }
+    }
+}
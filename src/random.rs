@@ -0,0 +1,43 @@
+const INCREMENT: u64 = 1442695040888963407;
+const MULTIPLIER: u64 = 6364136223846793005;
+
+/// A seedable PCG32 random number generator. Unlike macroquad's global `rand`, each generator
+/// owns its state, so the same seed always produces the same sequence.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Self { state: 0 };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let state = self.state;
+        self.state = state.wrapping_mul(MULTIPLIER).wrapping_add(INCREMENT);
+        let xorshifted = (((state >> 18) ^ state) >> 27) as u32;
+        let rotation = (state >> 59) as u32;
+        xorshifted.rotate_right(rotation)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        ((self.next_u32() as u64) << 32) | self.next_u32() as u64
+    }
+
+    /// A number in the range `low..high`.
+    pub fn gen_range(&mut self, low: f32, high: f32) -> f32 {
+        let t = self.next_u32() as f64 / (u32::MAX as f64 + 1.0);
+        (low as f64 + (high as f64 - low as f64) * t) as f32
+    }
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
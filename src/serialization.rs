@@ -0,0 +1,17 @@
+//! Serde helpers for types from macroquad which don't implement serde themselves.
+
+/// (De)serializes a [`Color`](macroquad::color::Color) as an `(r, g, b, a)` tuple. Use with
+/// `#[serde(with = "serialization::color")]`.
+pub mod color {
+    use macroquad::color::Color;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(color: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        [color.r, color.g, color.b, color.a].serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        let [r, g, b, a] = <[f32; 4]>::deserialize(deserializer)?;
+        Ok(Color::new(r, g, b, a))
+    }
+}
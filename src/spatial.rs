@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+
+use macroquad::math::Vec2;
+
+/// A uniform grid which buckets points by position, so nearby points can be found without
+/// checking every pair.
+#[derive(Clone, Debug)]
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl Default for SpatialGrid {
+    fn default() -> Self {
+        Self::new(1.)
+    }
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size: cell_size.max(f32::EPSILON),
+            cells: HashMap::new(),
+        }
+    }
+
+    /// Removes every point and changes the cell size. The cell's buckets are kept around to avoid
+    /// reallocating them every frame.
+    pub fn reset(&mut self, cell_size: f32) {
+        self.cell_size = cell_size.max(f32::EPSILON);
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+    }
+
+    pub fn insert(&mut self, index: usize, position: Vec2) {
+        self.cells
+            .entry(self.cell(position))
+            .or_default()
+            .push(index);
+    }
+
+    /// Calls `visitor` with every index inserted in a cell which overlaps the square of the given
+    /// half-size around `position`. This can include points slightly further away than `radius`.
+    pub fn query(&self, position: Vec2, radius: f32, mut visitor: impl FnMut(usize)) {
+        let (min_x, min_y) = self.cell(position - radius);
+        let (max_x, max_y) = self.cell(position + radius);
+        for x in min_x..=max_x {
+            for y in min_y..=max_y {
+                if let Some(cell) = self.cells.get(&(x, y)) {
+                    for &index in cell {
+                        visitor(index);
+                    }
+                }
+            }
+        }
+    }
+
+    fn cell(&self, position: Vec2) -> (i32, i32) {
+        let cell = (position / self.cell_size).floor();
+        (cell.x as i32, cell.y as i32)
+    }
+}
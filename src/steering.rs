@@ -0,0 +1,313 @@
+//! Steering behaviours, after Craig Reynolds' "Steering Behaviors For Autonomous Characters",
+//! which move the head of a body with a velocity rather than dragging it to a target.
+
+use macroquad::math::Vec2;
+
+use crate::{body::Body, random::Rng, spatial::SpatialGrid};
+
+/// What a steering agent knows about another creature.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Agent {
+    pub position: Vec2,
+    pub velocity: Vec2,
+}
+
+/// Everything around an agent which its behaviour may react to.
+#[derive(Clone, Copy, Debug)]
+pub struct SteeringContext<'a> {
+    /// The point sought, arrived at or fled from, e.g. the mouse.
+    pub target: Vec2,
+    /// Half of the size of the area the agent stays within, centred on the origin.
+    pub extent: Vec2,
+    /// Every creature in the world, which may be pursued or evaded.
+    pub agents: &'a [Agent],
+    /// Which of `agents` is being steered.
+    pub index: usize,
+    /// The indices of `agents`, bucketed by position with cells at least as large as the
+    /// flocking radius.
+    pub grid: &'a SpatialGrid,
+    pub flocking: &'a Flocking,
+}
+
+/// How creatures with [`SteeringBehaviour::Flock`] react to their neighbours.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Flocking {
+    /// How far away other creatures are noticed.
+    pub radius: f32,
+    /// How close other creatures can come before they're steered away from.
+    pub separation_radius: f32,
+    /// How strongly creatures steer away from neighbours which are too close.
+    pub separation: f32,
+    /// How strongly creatures steer to match the heading of their neighbours.
+    pub alignment: f32,
+    /// How strongly creatures steer towards the centre of their neighbours.
+    pub cohesion: f32,
+}
+
+impl Default for Flocking {
+    fn default() -> Self {
+        Self {
+            radius: 100.,
+            separation_radius: 40.,
+            separation: 2.,
+            alignment: 1.,
+            cohesion: 1.,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SteeringBehaviour {
+    /// Heads straight for the target at full speed.
+    Seek,
+    /// Heads for the target, slowing down within `slowing_radius` of it.
+    Arrive { slowing_radius: f32 },
+    /// Runs from the target while it's within `panic_radius`.
+    Flee { panic_radius: f32 },
+    /// Meanders by steering towards a point which drifts around a circle ahead of the agent.
+    Wander {
+        /// How far ahead of the agent the circle is.
+        distance: f32,
+        radius: f32,
+        /// How far, in radians per second, the point may drift.
+        jitter: f32,
+    },
+    /// Heads for where the nearest other creature is going to be.
+    Pursue,
+    /// Runs from where the nearest other creature is going to be, while it's within
+    /// `panic_radius`.
+    Evade { panic_radius: f32 },
+    /// Visits each point in turn, moving on once within `waypoint_radius` of it.
+    FollowPath {
+        points: Vec<Vec2>,
+        waypoint_radius: f32,
+        /// Whether to go back to the first point after the last, rather than stopping there.
+        looped: bool,
+    },
+    /// Moves with the other creatures around it, following the world's [`Flocking`] rules.
+    Flock,
+}
+
+/// Moves the head of a body with a velocity, which a [`SteeringBehaviour`] turns towards where
+/// it wants to go.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Steering {
+    pub behaviour: SteeringBehaviour,
+    /// The fastest the head moves, in units per second.
+    pub max_speed: f32,
+    /// The quickest the velocity changes, in units per second squared.
+    pub max_force: f32,
+    pub velocity: Vec2,
+    /// Where the wander point is on its circle, relative to the heading.
+    wander_angle: f32,
+    /// The path point being headed for.
+    waypoint: usize,
+    rng: Rng,
+}
+
+impl Steering {
+    pub fn new(behaviour: SteeringBehaviour) -> Self {
+        Self {
+            behaviour,
+            max_speed: 250.,
+            max_force: 600.,
+            velocity: Vec2::ZERO,
+            wander_angle: 0.,
+            waypoint: 0,
+            rng: Rng::default(),
+        }
+    }
+
+    /// Seeds the randomness used by [`SteeringBehaviour::Wander`].
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::new(seed);
+        self
+    }
+
+    /// The direction the head is moving in, in radians.
+    pub fn heading(&self) -> f32 {
+        self.velocity.to_angle()
+    }
+
+    /// Steers the first joint of the body for `dt` seconds, turning it to face its heading.
+    pub fn drive(&mut self, body: &mut Body, context: &SteeringContext, dt: f32) {
+        let Some(head) = body.joints.first_mut() else {
+            return;
+        };
+
+        // The behaviour still runs while out of bounds, so that wandering and path following
+        // keep their state.
+        let desired = self.desired_velocity(head.pos, context, dt);
+        let desired = self.contain(head.pos, context).unwrap_or(desired);
+        let force = (desired - self.velocity).clamp_length_max(self.max_force);
+        self.velocity = (self.velocity + force * dt).clamp_length_max(self.max_speed);
+
+        head.pos += self.velocity * dt;
+        if self.velocity.length_squared() > f32::EPSILON {
+            head.angle = self.heading();
+        }
+    }
+
+    /// The velocity the behaviour would like the head to have.
+    fn desired_velocity(&mut self, position: Vec2, context: &SteeringContext, dt: f32) -> Vec2 {
+        match &self.behaviour {
+            SteeringBehaviour::Seek => self.seek(position, context.target),
+            SteeringBehaviour::Arrive { slowing_radius } => {
+                self.arrive(position, context.target, *slowing_radius)
+            }
+            SteeringBehaviour::Flee { panic_radius } => {
+                self.flee(position, context.target, *panic_radius)
+            }
+            SteeringBehaviour::Wander {
+                distance,
+                radius,
+                jitter,
+            } => {
+                let (distance, radius, jitter) = (*distance, *radius, *jitter);
+                self.wander_angle += self.rng.gen_range(-jitter, jitter) * dt;
+                let heading = self.velocity.normalize_or(Vec2::X);
+                let target = position
+                    + heading * distance
+                    + radius * Vec2::from_angle(heading.to_angle() + self.wander_angle);
+                self.seek(position, target)
+            }
+            SteeringBehaviour::Pursue => match self.predict_nearest(position, context) {
+                Some(target) => self.seek(position, target),
+                None => Vec2::ZERO,
+            },
+            SteeringBehaviour::Evade { panic_radius } => {
+                match self.predict_nearest(position, context) {
+                    Some(target) => self.flee(position, target, *panic_radius),
+                    None => Vec2::ZERO,
+                }
+            }
+            SteeringBehaviour::FollowPath {
+                points,
+                waypoint_radius,
+                looped,
+            } => {
+                if points.is_empty() {
+                    return Vec2::ZERO;
+                }
+
+                self.waypoint = self.waypoint.min(points.len() - 1);
+                if position.distance(points[self.waypoint]) < *waypoint_radius {
+                    if self.waypoint + 1 < points.len() {
+                        self.waypoint += 1;
+                    } else if *looped {
+                        self.waypoint = 0;
+                    }
+                }
+
+                let target = points[self.waypoint];
+                if !looped && self.waypoint + 1 == points.len() {
+                    self.arrive(position, target, *waypoint_radius * 4.)
+                } else {
+                    self.seek(position, target)
+                }
+            }
+            SteeringBehaviour::Flock => self.flock(position, context),
+        }
+    }
+
+    fn seek(&self, position: Vec2, target: Vec2) -> Vec2 {
+        (target - position).normalize_or_zero() * self.max_speed
+    }
+
+    fn arrive(&self, position: Vec2, target: Vec2, slowing_radius: f32) -> Vec2 {
+        let distance = position.distance(target);
+        let speed = self.max_speed * (distance / slowing_radius.max(f32::EPSILON)).min(1.);
+        (target - position).normalize_or_zero() * speed
+    }
+
+    fn flee(&self, position: Vec2, target: Vec2, panic_radius: f32) -> Vec2 {
+        if position.distance(target) > panic_radius {
+            return Vec2::ZERO;
+        }
+        (position - target).normalize_or(Vec2::X) * self.max_speed
+    }
+
+    /// Blends carrying on ahead with separation from, alignment with and cohesion towards the
+    /// neighbours of the agent.
+    fn flock(&self, position: Vec2, context: &SteeringContext) -> Vec2 {
+        let flocking = context.flocking;
+        let mut separation = Vec2::ZERO;
+        let mut velocity = Vec2::ZERO;
+        let mut centre = Vec2::ZERO;
+        let mut neighbours = 0;
+        context.grid.query(position, flocking.radius, |index| {
+            if index == context.index {
+                return;
+            }
+
+            let other = context.agents[index];
+            let offset = position - other.position;
+            let distance = offset.length();
+            if distance > flocking.radius {
+                return;
+            }
+
+            if distance < flocking.separation_radius {
+                // Closer neighbours push harder.
+                separation +=
+                    offset.normalize_or_zero() * (1. - distance / flocking.separation_radius);
+            }
+            velocity += other.velocity;
+            centre += other.position;
+            neighbours += 1;
+        });
+
+        let cruise = self.velocity.normalize_or(Vec2::X) * self.max_speed;
+        if neighbours == 0 {
+            return cruise;
+        }
+
+        let count = neighbours as f32;
+        let rules = [
+            (1., cruise),
+            (
+                flocking.separation,
+                separation.normalize_or_zero() * self.max_speed,
+            ),
+            (
+                flocking.alignment,
+                (velocity / count).normalize_or_zero() * self.max_speed,
+            ),
+            (flocking.cohesion, self.seek(position, centre / count)),
+        ];
+
+        // Rules with nothing to say, like separation without anyone too close, are left out
+        // rather than slowing the agent down.
+        let (weight, desired) = rules
+            .into_iter()
+            .filter(|(_, desired)| *desired != Vec2::ZERO)
+            .fold((0., Vec2::ZERO), |(weight, sum), (rule_weight, desired)| {
+                (weight + rule_weight, sum + rule_weight * desired)
+            });
+        desired / weight.max(f32::EPSILON)
+    }
+
+    /// Where the nearest other agent will be by the time this one could reach it.
+    fn predict_nearest(&self, position: Vec2, context: &SteeringContext) -> Option<Vec2> {
+        let nearest = context
+            .agents
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| *index != context.index)
+            .map(|(_, agent)| agent)
+            .min_by(|a, b| {
+                a.position
+                    .distance_squared(position)
+                    .total_cmp(&b.position.distance_squared(position))
+            })?;
+
+        let time = position.distance(nearest.position) / self.max_speed.max(f32::EPSILON);
+        Some(nearest.position + nearest.velocity * time)
+    }
+
+    /// Heads back towards the origin once the agent strays outside of the extent.
+    fn contain(&self, position: Vec2, context: &SteeringContext) -> Option<Vec2> {
+        let outside = position.abs().cmpgt(context.extent);
+        outside.any().then(|| self.seek(position, Vec2::ZERO))
+    }
+}
@@ -0,0 +1,109 @@
+//! Triangulation of the lines drawn around bodies.
+
+use std::f32::consts::PI;
+
+use macroquad::math::Vec2;
+use serde::{Deserialize, Serialize};
+
+/// Beyond this ratio of a miter's length to half of the line's width, the corner is bevelled
+/// instead. This matches the SVG default.
+const MITER_LIMIT: f32 = 4.;
+/// The largest angle, in radians, covered by a single triangle of a round join.
+const ROUND_STEP: f32 = PI / 8.;
+
+/// How the line is shaped where it turns a corner.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LineJoin {
+    /// The edges are extended until they meet, or bevelled when that would be too far out.
+    Miter,
+    /// The corner is rounded off.
+    #[default]
+    Round,
+}
+
+/// Triangles which draw a line.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stroke {
+    pub vertices: Vec<Vec2>,
+    /// Every three indices into `vertices` make up a triangle. These are wider than a mesh's
+    /// indices so that long outlines can't wrap around, and are split up when drawn.
+    pub indices: Vec<u32>,
+}
+
+/// Triangulates a band of the given width just outside the closed polygon, so it can be drawn
+/// under or alongside the polygon's fill without the two overlapping.
+pub fn stroke_outline(outline: &[Vec2], width: f32, join: LineJoin) -> Stroke {
+    let mut points = outline.to_vec();
+    points.dedup_by(|a, b| a.distance_squared(*b) <= f32::EPSILON);
+    while points.len() > 1 && points[0].distance_squared(points[points.len() - 1]) <= f32::EPSILON {
+        points.pop();
+    }
+
+    let count = points.len();
+    if count < 3 || width <= 0. {
+        return Stroke::default();
+    }
+
+    // Outward is to the right of each edge for anti-clockwise polygons, and to the left otherwise.
+    let area: f32 = (0..count)
+        .map(|i| points[i].perp_dot(points[(i + 1) % count]))
+        .sum();
+    let outward = |edge: Vec2| -area.signum() * edge.perp().normalize_or_zero();
+
+    let mut stroke = Stroke::default();
+    // The first and last outer vertex around each corner, for joining up the edges between them.
+    let mut corners = Vec::with_capacity(count);
+    for i in 0..count {
+        let point = points[i];
+        let before = point - points[(i + count - 1) % count];
+        let after = points[(i + 1) % count] - point;
+        let (from, to) = (outward(before), outward(after));
+
+        let inner = stroke.vertices.len() as u32;
+        stroke.vertices.push(point);
+        let first = inner + 1;
+        for offset in join_offsets(from, to, before.perp_dot(after) * area > 0., join) {
+            stroke.vertices.push(point + offset * width);
+        }
+        let last = stroke.vertices.len() as u32 - 1;
+
+        for outer in first..last {
+            stroke.indices.extend([inner, outer, outer + 1]);
+        }
+        corners.push((inner, first, last));
+    }
+
+    for i in 0..count {
+        let (inner, _, last) = corners[i];
+        let (next_inner, next_first, _) = corners[(i + 1) % count];
+        stroke
+            .indices
+            .extend([inner, last, next_first, inner, next_first, next_inner]);
+    }
+
+    stroke
+}
+
+/// The offsets, for a line of unit width, of the outer edge of the line around a corner where the
+/// outward normal turns from `from` to `to`.
+fn join_offsets(from: Vec2, to: Vec2, convex: bool, join: LineJoin) -> Vec<Vec2> {
+    let bisector = (from + to).normalize_or_zero();
+    let cos_half_angle = bisector.dot(from);
+    let miter = (cos_half_angle * MITER_LIMIT > 1.).then(|| bisector / cos_half_angle);
+
+    match (convex, join, miter) {
+        // The edges overlap on the inside of the turn, so they're joined where they cross.
+        (false, _, Some(miter)) | (true, LineJoin::Miter, Some(miter)) => vec![miter],
+        // Where they'd cross too far out, they're joined short of it instead. This thins the line
+        // into the corner, but unlike separate ends for each edge, doesn't overlap itself.
+        (false, _, None) => vec![bisector * MITER_LIMIT],
+        (true, LineJoin::Round, _) => {
+            let angle = from.angle_between(to);
+            let steps = (angle.abs() / ROUND_STEP).ceil().max(1.) as usize;
+            (0..=steps)
+                .map(|step| Vec2::from_angle(angle * step as f32 / steps as f32).rotate(from))
+                .collect()
+        }
+        _ => vec![from, to],
+    }
+}
@@ -0,0 +1,46 @@
+/// Accumulates frame time and hands it out in fixed steps, so the simulation behaves the same
+/// regardless of the refresh rate.
+#[derive(Clone, Copy, Debug)]
+pub struct FixedTimestep {
+    /// The duration of a single step, in seconds.
+    pub step: f32,
+    /// The maximum number of steps to take in a single frame. Any time beyond this is dropped,
+    /// rather than letting slow frames snowball.
+    pub max_steps: u32,
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    pub fn new(step: f32) -> Self {
+        Self {
+            step,
+            max_steps: 8,
+            accumulator: 0.,
+        }
+    }
+
+    /// Adds the elapsed frame time and returns the number of fixed steps to simulate.
+    pub fn advance(&mut self, frame_time: f32) -> u32 {
+        self.accumulator += frame_time.max(0.);
+
+        let steps = (self.accumulator / self.step).floor() as u32;
+        if steps > self.max_steps {
+            self.accumulator = 0.;
+            return self.max_steps;
+        }
+
+        self.accumulator -= steps as f32 * self.step;
+        steps
+    }
+}
+
+/// The fraction to move towards a target over `dt` seconds, such that half of the remaining
+/// distance is covered every `half_life` seconds. A half-life of zero (or less) snaps straight to
+/// the target.
+pub fn blend_factor(half_life: f32, dt: f32) -> f32 {
+    if half_life <= 0. {
+        return 1.;
+    }
+
+    1. - (-dt / half_life).exp2()
+}
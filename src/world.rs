@@ -0,0 +1,237 @@
+use macroquad::math::Vec2;
+
+use crate::{
+    body::Body,
+    constants::HEAD_HALF_LIFE,
+    constraints::collide_bodies,
+    obstacle::{avoid_obstacles, Obstacle},
+    path::{self, SplinePath},
+    spatial::SpatialGrid,
+    steering::{Agent, Flocking, Steering, SteeringBehaviour, SteeringContext},
+    timestep::blend_factor,
+};
+
+/// Decides where the head of a creature moves to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum HeadController {
+    /// Follows the figure-of-eight from [`path::lemniscate`], scaled to the world's extent and
+    /// shifted in time by `phase` seconds.
+    Lemniscate { phase: f32 },
+    /// Follows a spline path, starting from its beginning when the world's time is zero.
+    Spline(SplinePath),
+    /// Follows the world's pointer, e.g. the mouse.
+    Pointer,
+    /// Moves the head with a velocity from a steering behaviour, which seeks, flees and so on
+    /// from the world's pointer or from other creatures.
+    Steering(Steering),
+    /// Leaves the head where it is.
+    Idle,
+}
+
+impl HeadController {
+    /// Where the head should move towards, if anywhere. Steered heads have no target, as they
+    /// move with a velocity instead.
+    pub fn target(&self, world_time: f32, extent: Vec2, pointer: Vec2) -> Option<Vec2> {
+        match self {
+            HeadController::Lemniscate { phase } => {
+                Some(path::lemniscate(world_time + phase) * extent / 2.)
+            }
+            HeadController::Spline(path) => path.position(world_time),
+            HeadController::Pointer => Some(pointer),
+            HeadController::Steering(_) | HeadController::Idle => None,
+        }
+    }
+}
+
+/// A body in the world, along with whatever drives it. `T` is any extra data the owner of the
+/// world wants to keep with each creature, such as the configuration it was built from.
+#[derive(Clone, Debug)]
+pub struct Creature<T = ()> {
+    pub body: Body,
+    pub controller: HeadController,
+    /// The time, in seconds, taken for the head to cover half of the distance to its target.
+    pub head_half_life: f32,
+    /// How fast the head moved during the last step, in units per second.
+    pub velocity: Vec2,
+    pub data: T,
+}
+
+impl<T> Creature<T> {
+    pub fn new(body: Body, controller: HeadController, data: T) -> Self {
+        Self {
+            body,
+            controller,
+            head_half_life: HEAD_HALF_LIFE,
+            velocity: Vec2::ZERO,
+            data,
+        }
+    }
+
+    /// The position of the first joint, or the origin if the body has no joints.
+    pub fn head(&self) -> Vec2 {
+        self.body
+            .joints
+            .first()
+            .map_or(Vec2::ZERO, |joint| joint.pos)
+    }
+}
+
+/// Holds and steps many creatures at once.
+#[derive(Clone, Debug)]
+pub struct World<T = ()> {
+    pub creatures: Vec<Creature<T>>,
+    /// Half of the size of the area the creatures roam, centred on the origin.
+    pub extent: Vec2,
+    /// The position followed by creatures with [`HeadController::Pointer`].
+    pub pointer: Vec2,
+    /// The simulated time, in seconds.
+    pub time: f32,
+    /// Whether joints of different creatures are pushed apart when they overlap.
+    pub collisions: bool,
+    /// The time, in seconds, taken to correct half of the overlap between creatures.
+    pub collision_half_life: f32,
+    /// Static geometry which every joint of every creature is kept out of.
+    pub obstacles: Vec<Obstacle>,
+    /// The time, in seconds, taken to correct half of the overlap with an obstacle.
+    pub obstacle_half_life: f32,
+    /// The rules followed by creatures with [`SteeringBehaviour::Flock`].
+    pub flocking: Flocking,
+    grid: SpatialGrid,
+    /// Buckets the heads of creatures by position, for finding their neighbours when flocking.
+    flock_grid: SpatialGrid,
+}
+
+impl<T> Default for World<T> {
+    fn default() -> Self {
+        Self {
+            creatures: Vec::new(),
+            extent: Vec2::ONE,
+            pointer: Vec2::ZERO,
+            time: 0.,
+            collisions: true,
+            collision_half_life: 0.,
+            obstacles: Vec::new(),
+            obstacle_half_life: 0.,
+            flocking: Flocking::default(),
+            grid: SpatialGrid::default(),
+            flock_grid: SpatialGrid::default(),
+        }
+    }
+}
+
+impl<T> World<T> {
+    pub fn new(extent: Vec2) -> Self {
+        Self {
+            extent,
+            ..Default::default()
+        }
+    }
+
+    /// Adds a creature to the world, returning its index.
+    pub fn spawn(&mut self, creature: Creature<T>) -> usize {
+        self.creatures.push(creature);
+        self.creatures.len() - 1
+    }
+
+    pub fn remove(&mut self, index: usize) -> Creature<T> {
+        self.creatures.remove(index)
+    }
+
+    /// Steps every creature forward by `dt` seconds.
+    pub fn step(&mut self, dt: f32) {
+        self.time += dt;
+
+        let agents: Vec<Agent> = self
+            .creatures
+            .iter()
+            .map(|creature| Agent {
+                position: creature.head(),
+                velocity: creature.velocity,
+            })
+            .collect();
+
+        let flocks = self.creatures.iter().any(|creature| {
+            matches!(
+                &creature.controller,
+                HeadController::Steering(Steering {
+                    behaviour: SteeringBehaviour::Flock,
+                    ..
+                })
+            )
+        });
+        if flocks {
+            self.flock_grid.reset(self.flocking.radius);
+            for (index, agent) in agents.iter().enumerate() {
+                self.flock_grid.insert(index, agent.position);
+            }
+        }
+
+        for (index, creature) in self.creatures.iter_mut().enumerate() {
+            creature.body.apply_constraints(None, dt);
+
+            if let HeadController::Steering(steering) = &mut creature.controller {
+                let context = SteeringContext {
+                    target: self.pointer,
+                    extent: self.extent,
+                    agents: &agents,
+                    index,
+                    grid: &self.flock_grid,
+                    flocking: &self.flocking,
+                };
+                steering.drive(&mut creature.body, &context, dt);
+            } else if let Some(target) =
+                creature
+                    .controller
+                    .target(self.time, self.extent, self.pointer)
+            {
+                path::follow(&mut creature.body, target, creature.head_half_life, dt);
+            }
+        }
+
+        if self.collisions {
+            collide_bodies(
+                self.creatures.iter_mut().map(|creature| &mut creature.body),
+                self.collision_half_life,
+                dt,
+                &mut self.grid,
+            );
+        }
+
+        if !self.obstacles.is_empty() {
+            let rate = blend_factor(self.obstacle_half_life, dt);
+            for creature in &mut self.creatures {
+                creature
+                    .body
+                    .visit_joints_mut(&mut |joint| avoid_obstacles(joint, &self.obstacles, rate));
+            }
+        }
+
+        if dt > 0. {
+            for (creature, agent) in self.creatures.iter_mut().zip(&agents) {
+                creature.velocity = (creature.head() - agent.position) / dt;
+            }
+        }
+    }
+
+    #[cfg(feature = "render")]
+    pub fn draw(&self) {
+        for creature in &self.creatures {
+            creature.body.draw();
+        }
+    }
+
+    #[cfg(feature = "render")]
+    pub fn debug_draw(&self) {
+        for creature in &self.creatures {
+            creature.body.debug_draw();
+
+            if let HeadController::Spline(path) = &creature.controller {
+                path.debug_draw();
+            }
+        }
+
+        for obstacle in &self.obstacles {
+            obstacle.debug_draw();
+        }
+    }
+}